@@ -0,0 +1,219 @@
+use std::process::{Command, Stdio};
+
+use anyhow::{anyhow, Result};
+
+use super::{Backend, Branch};
+
+/// Runs a git subprocess for every operation.
+pub struct GitCli;
+
+impl Backend for GitCli {
+    fn local_branches(&self) -> Result<Vec<Branch>> {
+        let git_output = Command::new("git")
+            .args([
+                "for-each-ref",
+                concat!(
+                    "--format=%(refname:short)%09%(objectname)%09%(committerdate:unix)",
+                    "%09%(authorname)%09%(authoremail:trim)%09%(upstream:short)%09%(upstream:track)",
+                    "%09%(contents:subject)",
+                ),
+                "refs/heads",
+            ])
+            .output()?
+            .stdout;
+        String::from_utf8(git_output)?
+            .lines()
+            .map(|line| {
+                let mut fields = line.splitn(8, '\t');
+                let mut next_field = || {
+                    fields
+                        .next()
+                        .ok_or_else(|| anyhow!("unexpected output of git for-each-ref: {line}"))
+                };
+                Ok(Branch {
+                    name: next_field()?.to_owned(),
+                    commit: next_field()?.to_owned(),
+                    committer_date: next_field()?.parse()?,
+                    author_name: next_field()?.to_owned(),
+                    author_email: next_field()?.to_owned(),
+                    upstream: Some(next_field()?)
+                        .filter(|upstream| !upstream.is_empty())
+                        .map(str::to_owned),
+                    upstream_track: next_field()?.to_owned(),
+                    subject: next_field()?.to_owned(),
+                })
+            })
+            .collect()
+    }
+
+    fn current_branch(&self) -> Result<String> {
+        let mut git_output = Command::new("git")
+            .args(["branch", "--show-current"])
+            .output()?
+            .stdout;
+        git_output.pop();
+        Ok(String::from_utf8(git_output)?)
+    }
+
+    fn resolve(&self, rev: &str) -> Result<Option<String>> {
+        let output = Command::new("git")
+            .args([
+                "rev-parse",
+                "--verify",
+                "--quiet",
+                &format!("{rev}^{{commit}}"),
+            ])
+            .output()?;
+        if !output.status.success() {
+            return Ok(None);
+        }
+        Ok(Some(String::from_utf8(output.stdout)?.trim().to_owned()))
+    }
+
+    fn tree_of(&self, rev: &str) -> Result<String> {
+        let cat_file_output = Command::new("git").args(["cat-file", "-p", rev]).output()?;
+        Ok(String::from_utf8(cat_file_output.stdout)?
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .trim_start_matches("tree ")
+            .to_owned())
+    }
+
+    fn merge_base(&self, a: &str, b: &str) -> Result<Option<String>> {
+        let output = Command::new("git").args(["merge-base", a, b]).output()?;
+        if !output.status.success() {
+            // unrelated histories
+            return Ok(None);
+        }
+        Ok(Some(String::from_utf8(output.stdout)?.trim().to_owned()))
+    }
+
+    fn is_ancestor(&self, ancestor: &str, descendant: &str) -> Result<bool> {
+        Ok(Command::new("git")
+            .args(["merge-base", "--is-ancestor", ancestor, descendant])
+            .output()?
+            .status
+            .success())
+    }
+
+    fn ahead_behind(&self, branch: &str, base: &str) -> Result<(usize, usize)> {
+        let output = Command::new("git")
+            .args([
+                "rev-list",
+                "--left-right",
+                "--count",
+                &format!("{branch}...{base}"),
+            ])
+            .output()?;
+        let counts = String::from_utf8(output.stdout)?;
+        match counts.split_whitespace().collect::<Vec<_>>()[..] {
+            [ahead, behind] => Ok((ahead.parse()?, behind.parse()?)),
+            _ => Err(anyhow!(
+                "failed to compare '{branch}' with '{base}':\n{}",
+                String::from_utf8(output.stderr)?.trim_end()
+            )),
+        }
+    }
+
+    fn first_parent_history(
+        &self,
+        tip: &str,
+        stop: &str,
+        max_count: usize,
+    ) -> Result<Vec<(String, String)>> {
+        let log_output = Command::new("git")
+            .args([
+                "log",
+                "--first-parent",
+                &format!("--max-count={max_count}"),
+                "--format=%H %T",
+                tip,
+                &format!("^{stop}"),
+            ])
+            .output()?;
+        Ok(String::from_utf8(log_output.stdout)?
+            .lines()
+            .filter_map(|line| line.split_once(' '))
+            .map(|(commit, tree)| (commit.to_owned(), tree.to_owned()))
+            .collect())
+    }
+
+    fn merge_trees(&self, base: &str, branch: &str) -> Result<Option<String>> {
+        let merge_tree_output = Command::new("git")
+            .args(["merge-tree", base, branch])
+            .output()?;
+        if !merge_tree_output.status.success() {
+            // merge-tree reported conflict.
+            return Ok(None);
+        }
+        Ok(Some(
+            String::from_utf8(merge_tree_output.stdout)?
+                .trim()
+                .to_owned(),
+        ))
+    }
+
+    fn diff_patch_id(&self, old: &str, new: &str) -> Result<Option<String>> {
+        // plumbing, so user settings like color.ui or diff.noprefix
+        // don't change the patches
+        let mut diff = Command::new("git");
+        diff.args(["diff-tree", "-p", old, new]);
+        Ok(patch_ids(diff)?
+            .into_iter()
+            .next()
+            .map(|(_, patch_id)| patch_id))
+    }
+
+    fn patch_ids(
+        &self,
+        tip: &str,
+        stop: Option<&str>,
+        max_count: usize,
+    ) -> Result<Vec<(String, String)>> {
+        let mut commits = Command::new("git")
+            .args([
+                "rev-list",
+                "--no-merges",
+                &format!("--max-count={max_count}"),
+                tip,
+            ])
+            .args(stop.map(|stop| format!("^{stop}")))
+            .stdout(Stdio::piped())
+            .spawn()?;
+        let mut patches = Command::new("git");
+        patches
+            .args(["diff-tree", "--stdin", "-p"])
+            .stdin(commits.stdout.take().unwrap());
+        let patch_ids = patch_ids(patches)?;
+        commits.wait()?;
+        Ok(patch_ids)
+    }
+
+    fn delete_branch(&self, branch: &str) -> Result<()> {
+        let output = Command::new("git")
+            .args(["branch", "--delete", "--force", branch])
+            .output()?;
+        if !output.status.success() {
+            return Err(anyhow!("{}", String::from_utf8(output.stderr)?.trim_end()));
+        }
+        Ok(())
+    }
+}
+
+/// Computes the stable patch-ids of the patches printed by `patches`,
+/// along with the commit of each patch. Its output is piped straight into
+/// `git patch-id`, so neither of them blocks on a full pipe.
+fn patch_ids(mut patches: Command) -> Result<Vec<(String, String)>> {
+    let mut patches = patches.stdout(Stdio::piped()).spawn()?;
+    let output = Command::new("git")
+        .args(["patch-id", "--stable"])
+        .stdin(patches.stdout.take().unwrap())
+        .output()?;
+    patches.wait()?;
+    Ok(String::from_utf8(output.stdout)?
+        .lines()
+        .filter_map(|line| line.split_once(' '))
+        .map(|(patch_id, commit)| (commit.to_owned(), patch_id.to_owned()))
+        .collect())
+}
@@ -0,0 +1,254 @@
+use std::cell::RefCell;
+
+use anyhow::{anyhow, Result};
+use git2::{BranchType, Diff, ErrorCode, Oid, Reference, Repository, Sort};
+
+use super::{Backend, Branch};
+
+/// Runs every operation in-process with libgit2.
+pub struct Libgit2;
+
+impl Libgit2 {
+    pub fn open() -> Result<Self> {
+        // fail early if we're not in a repository
+        Repository::open_from_env()?;
+        Ok(Self)
+    }
+
+    /// Runs `f` with a repository handle of the current thread,
+    /// because a [Repository] cannot be shared between threads.
+    fn with_repo<T>(&self, f: impl FnOnce(&Repository) -> Result<T>) -> Result<T> {
+        thread_local! {
+            static REPO: RefCell<Option<Repository>> = const { RefCell::new(None) };
+        }
+        REPO.with_borrow_mut(|repo| {
+            if repo.is_none() {
+                *repo = Some(Repository::open_from_env()?);
+            }
+            f(repo.as_ref().unwrap())
+        })
+    }
+}
+
+fn resolve_commit(repo: &Repository, rev: &str) -> Result<Oid> {
+    Ok(repo.revparse_single(rev)?.peel_to_commit()?.id())
+}
+
+/// Returns the short name of the upstream branch and the tracking status
+/// in the format of `git for-each-ref`.
+fn upstream_of(
+    repo: &Repository,
+    branch: &Reference,
+    tip: Oid,
+) -> Result<(Option<String>, String)> {
+    let Some(refname) = branch.name() else {
+        return Ok((None, String::new()));
+    };
+    let upstream = match repo.branch_upstream_name(refname) {
+        Ok(upstream) => upstream.as_str().unwrap_or_default().to_owned(),
+        Err(e) if e.code() == ErrorCode::NotFound => return Ok((None, String::new())),
+        Err(e) => return Err(e.into()),
+    };
+    let short_name = upstream
+        .strip_prefix("refs/remotes/")
+        .or_else(|| upstream.strip_prefix("refs/heads/"))
+        .unwrap_or(&upstream)
+        .to_owned();
+
+    let upstream_tip = match repo.refname_to_id(&upstream) {
+        Ok(upstream_tip) => upstream_tip,
+        Err(e) if e.code() == ErrorCode::NotFound => {
+            return Ok((Some(short_name), "[gone]".into()))
+        }
+        Err(e) => return Err(e.into()),
+    };
+    let track = match repo.graph_ahead_behind(tip, upstream_tip)? {
+        (0, 0) => String::new(),
+        (ahead, 0) => format!("[ahead {ahead}]"),
+        (0, behind) => format!("[behind {behind}]"),
+        (ahead, behind) => format!("[ahead {ahead}, behind {behind}]"),
+    };
+    Ok((Some(short_name), track))
+}
+
+/// `None` if the diff is empty, like `git patch-id` skips empty patches.
+fn patch_id(diff: &Diff) -> Result<Option<String>> {
+    if diff.deltas().len() == 0 {
+        return Ok(None);
+    }
+    Ok(Some(diff.patchid(None)?.to_string()))
+}
+
+impl Backend for Libgit2 {
+    fn local_branches(&self) -> Result<Vec<Branch>> {
+        self.with_repo(|repo| {
+            let mut branches = Vec::new();
+            for branch in repo.branches(Some(BranchType::Local))? {
+                let (branch, _) = branch?;
+                let Some(name) = branch.name()? else {
+                    continue;
+                };
+                let tip = branch.get().peel_to_commit()?;
+                let (upstream, upstream_track) = upstream_of(repo, branch.get(), tip.id())?;
+                branches.push(Branch {
+                    name: name.to_owned(),
+                    commit: tip.id().to_string(),
+                    committer_date: tip.committer().when().seconds(),
+                    author_name: tip.author().name().unwrap_or_default().to_owned(),
+                    author_email: tip.author().email().unwrap_or_default().to_owned(),
+                    upstream,
+                    upstream_track,
+                    subject: tip.summary().unwrap_or_default().to_owned(),
+                });
+            }
+            branches.sort_by(|a, b| a.name.cmp(&b.name));
+            Ok(branches)
+        })
+    }
+
+    fn current_branch(&self) -> Result<String> {
+        self.with_repo(|repo| match repo.head() {
+            Ok(head) if head.is_branch() => Ok(head.shorthand().unwrap_or_default().to_owned()),
+            Ok(_) => Ok(String::new()),
+            Err(e) if e.code() == ErrorCode::UnbornBranch => {
+                // HEAD points to a branch without commits
+                let head = repo.find_reference("HEAD")?;
+                let target = head.symbolic_target().unwrap_or_default();
+                Ok(target.trim_start_matches("refs/heads/").to_owned())
+            }
+            Err(e) => Err(e.into()),
+        })
+    }
+
+    fn resolve(&self, rev: &str) -> Result<Option<String>> {
+        self.with_repo(|repo| match repo.revparse_single(rev) {
+            Ok(object) => Ok(Some(object.peel_to_commit()?.id().to_string())),
+            Err(e) if e.code() == ErrorCode::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        })
+    }
+
+    fn tree_of(&self, rev: &str) -> Result<String> {
+        self.with_repo(|repo| Ok(repo.revparse_single(rev)?.peel_to_tree()?.id().to_string()))
+    }
+
+    fn merge_base(&self, a: &str, b: &str) -> Result<Option<String>> {
+        self.with_repo(|repo| {
+            let a = resolve_commit(repo, a)?;
+            let b = resolve_commit(repo, b)?;
+            match repo.merge_base(a, b) {
+                Ok(merge_base) => Ok(Some(merge_base.to_string())),
+                // unrelated histories
+                Err(e) if e.code() == ErrorCode::NotFound => Ok(None),
+                Err(e) => Err(e.into()),
+            }
+        })
+    }
+
+    fn is_ancestor(&self, ancestor: &str, descendant: &str) -> Result<bool> {
+        self.with_repo(|repo| {
+            let ancestor = resolve_commit(repo, ancestor)?;
+            let descendant = resolve_commit(repo, descendant)?;
+            Ok(ancestor == descendant || repo.graph_descendant_of(descendant, ancestor)?)
+        })
+    }
+
+    fn ahead_behind(&self, branch: &str, base: &str) -> Result<(usize, usize)> {
+        self.with_repo(|repo| {
+            let branch = resolve_commit(repo, branch)?;
+            let base = resolve_commit(repo, base)?;
+            Ok(repo.graph_ahead_behind(branch, base)?)
+        })
+    }
+
+    fn first_parent_history(
+        &self,
+        tip: &str,
+        stop: &str,
+        max_count: usize,
+    ) -> Result<Vec<(String, String)>> {
+        self.with_repo(|repo| {
+            let mut walk = repo.revwalk()?;
+            walk.set_sorting(Sort::TOPOLOGICAL)?;
+            walk.simplify_first_parent()?;
+            walk.push(resolve_commit(repo, tip)?)?;
+            walk.hide(resolve_commit(repo, stop)?)?;
+            walk.take(max_count)
+                .map(|commit| {
+                    let commit = repo.find_commit(commit?)?;
+                    Ok((commit.id().to_string(), commit.tree_id().to_string()))
+                })
+                .collect()
+        })
+    }
+
+    fn merge_trees(&self, base: &str, branch: &str) -> Result<Option<String>> {
+        self.with_repo(|repo| {
+            let base = repo.find_commit(resolve_commit(repo, base)?)?;
+            let branch = repo.find_commit(resolve_commit(repo, branch)?)?;
+            let mut index = repo.merge_commits(&base, &branch, None)?;
+            if index.has_conflicts() {
+                return Ok(None);
+            }
+            Ok(Some(index.write_tree_to(repo)?.to_string()))
+        })
+    }
+
+    fn diff_patch_id(&self, old: &str, new: &str) -> Result<Option<String>> {
+        self.with_repo(|repo| {
+            let old = repo.revparse_single(old)?.peel_to_tree()?;
+            let new = repo.revparse_single(new)?.peel_to_tree()?;
+            patch_id(&repo.diff_tree_to_tree(Some(&old), Some(&new), None)?)
+        })
+    }
+
+    fn patch_ids(
+        &self,
+        tip: &str,
+        stop: Option<&str>,
+        max_count: usize,
+    ) -> Result<Vec<(String, String)>> {
+        self.with_repo(|repo| {
+            let mut walk = repo.revwalk()?;
+            walk.push(resolve_commit(repo, tip)?)?;
+            if let Some(stop) = stop {
+                walk.hide(resolve_commit(repo, stop)?)?;
+            }
+            let mut patch_ids = Vec::new();
+            let mut num_commits = 0;
+            for commit in walk {
+                let commit = repo.find_commit(commit?)?;
+                if commit.parent_count() > 1 {
+                    continue;
+                }
+                if num_commits == max_count {
+                    break;
+                }
+                num_commits += 1;
+                // like git diff-tree without --root
+                let Ok(parent) = commit.parent(0) else {
+                    continue;
+                };
+                let diff =
+                    repo.diff_tree_to_tree(Some(&parent.tree()?), Some(&commit.tree()?), None)?;
+                if let Some(patch_id) = patch_id(&diff)? {
+                    patch_ids.push((commit.id().to_string(), patch_id));
+                }
+            }
+            Ok(patch_ids)
+        })
+    }
+
+    fn delete_branch(&self, branch: &str) -> Result<()> {
+        self.with_repo(|repo| {
+            let mut local_branch = repo.find_branch(branch, BranchType::Local)?;
+            if local_branch.is_head() {
+                return Err(anyhow!(
+                    "error: cannot delete checked out branch '{branch}'"
+                ));
+            }
+            local_branch.delete()?;
+            Ok(())
+        })
+    }
+}
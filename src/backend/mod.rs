@@ -0,0 +1,90 @@
+//! Access to the repository, either by running the git CLI or in-process
+//! with libgit2 (enabled with the `libgit2` feature).
+
+use anyhow::Result;
+
+#[cfg(not(feature = "libgit2"))]
+mod cli;
+#[cfg(feature = "libgit2")]
+mod libgit2;
+
+/// A local branch along with information about its tip.
+#[derive(Debug, Clone)]
+pub struct Branch {
+    /// The short name, e.g. `feature/thing`.
+    pub name: String,
+    pub commit: String,
+    /// Unix timestamp of the tip's committer date.
+    pub committer_date: i64,
+    pub author_name: String,
+    pub author_email: String,
+    /// The short name of the upstream branch, if one is configured.
+    pub upstream: Option<String>,
+    /// How the branch relates to its upstream in the format of git,
+    /// e.g. `[ahead 1, behind 2]` or `[gone]`. Empty if in sync.
+    pub upstream_track: String,
+    /// The first line of the tip's commit message.
+    pub subject: String,
+}
+
+/// The operations on the repository that run for every branch.
+/// Everything else is rare enough to simply run the git CLI.
+pub trait Backend: Sync {
+    /// All local branches, sorted by name.
+    fn local_branches(&self) -> Result<Vec<Branch>>;
+
+    /// Empty in detached HEAD state.
+    fn current_branch(&self) -> Result<String>;
+
+    /// Resolves a revision to a commit id, `None` if it doesn't exist.
+    fn resolve(&self, rev: &str) -> Result<Option<String>>;
+
+    /// The id of the tree of a commit.
+    fn tree_of(&self, rev: &str) -> Result<String>;
+
+    /// `None` if the histories are unrelated.
+    fn merge_base(&self, a: &str, b: &str) -> Result<Option<String>>;
+
+    fn is_ancestor(&self, ancestor: &str, descendant: &str) -> Result<bool>;
+
+    /// How many commits `branch` has that `base` doesn't and vice versa.
+    fn ahead_behind(&self, branch: &str, base: &str) -> Result<(usize, usize)>;
+
+    /// The first-parent history of `tip` down to (excluding) `stop`,
+    /// at most `max_count` pairs of commit and tree ids.
+    fn first_parent_history(
+        &self,
+        tip: &str,
+        stop: &str,
+        max_count: usize,
+    ) -> Result<Vec<(String, String)>>;
+
+    /// Merges two commits and returns the id of the resulting tree,
+    /// `None` if the merge has conflicts.
+    fn merge_trees(&self, base: &str, branch: &str) -> Result<Option<String>>;
+
+    /// The patch-id of the diff between two commits,
+    /// `None` if their trees are the same.
+    fn diff_patch_id(&self, old: &str, new: &str) -> Result<Option<String>>;
+
+    /// Pairs of commit id and patch-id of the non-merge commits of `tip`
+    /// down to (excluding) `stop`, at most `max_count` commits. Root commits
+    /// and commits without changes are skipped.
+    fn patch_ids(
+        &self,
+        tip: &str,
+        stop: Option<&str>,
+        max_count: usize,
+    ) -> Result<Vec<(String, String)>>;
+
+    /// Force-deletes a local branch.
+    fn delete_branch(&self, branch: &str) -> Result<()>;
+}
+
+pub fn open() -> Result<Box<dyn Backend>> {
+    #[cfg(feature = "libgit2")]
+    return Ok(Box::new(libgit2::Libgit2::open()?));
+
+    #[cfg(not(feature = "libgit2"))]
+    Ok(Box::new(cli::GitCli))
+}
@@ -0,0 +1,90 @@
+//! Defaults read from `git config`, so they don't have to be passed on
+//! every invocation. Command line flags take precedence.
+//! As usual, repository settings override global and system ones.
+
+use std::{collections::HashMap, process::Command};
+
+use anyhow::{anyhow, Result};
+
+#[derive(Debug, Default)]
+pub struct Config {
+    /// `quickprune.mainBranch`
+    pub main_branch: Option<String>,
+    /// `quickprune.remote`
+    pub remote: Option<String>,
+    /// `quickprune.upstream`
+    pub upstream: Option<String>,
+    /// `remote.pushDefault`
+    pub push_default: Option<String>,
+    /// `branch.<name>.pushRemote` by branch name
+    pub push_remotes: HashMap<String, String>,
+    /// `quickprune.deleteRemote`
+    pub delete_remote: Option<bool>,
+    /// `quickprune.protect`, may be set multiple times
+    pub protect: Vec<String>,
+    /// `quickprune.base`, may be set multiple times
+    pub base: Vec<String>,
+}
+
+impl Config {
+    pub fn load() -> Result<Self> {
+        Ok(Self {
+            main_branch: get("quickprune.mainBranch")?,
+            remote: get("quickprune.remote")?,
+            upstream: get("quickprune.upstream")?,
+            push_default: get("remote.pushDefault")?,
+            push_remotes: get_regexp(r"^branch\..*\.pushremote$")?
+                .into_iter()
+                .filter_map(|(key, value)| {
+                    let branch = key.strip_prefix("branch.")?.strip_suffix(".pushremote")?;
+                    Some((branch.to_owned(), value))
+                })
+                .collect(),
+            delete_remote: get_bool("quickprune.deleteRemote")?,
+            protect: get_all("quickprune.protect")?,
+            base: get_all("quickprune.base")?,
+        })
+    }
+}
+
+fn get(key: &str) -> Result<Option<String>> {
+    git_config(&["--get", key])
+}
+
+fn get_all(key: &str) -> Result<Vec<String>> {
+    Ok(git_config(&["--get-all", key])?
+        .map(|values| values.lines().map(str::to_owned).collect())
+        .unwrap_or_default())
+}
+
+/// All keys matching a regex along with their values. Git lowercases
+/// the section and variable names, but not the subsection, e.g. the
+/// branch name in `branch.Feature.pushremote`.
+fn get_regexp(regex: &str) -> Result<Vec<(String, String)>> {
+    Ok(git_config(&["--get-regexp", regex])?
+        .map(|lines| {
+            lines
+                .lines()
+                .filter_map(|line| line.split_once(' '))
+                .map(|(key, value)| (key.to_owned(), value.to_owned()))
+                .collect()
+        })
+        .unwrap_or_default())
+}
+
+fn get_bool(key: &str) -> Result<Option<bool>> {
+    Ok(git_config(&["--type=bool", "--get", key])?.map(|value| value == "true"))
+}
+
+fn git_config(args: &[&str]) -> Result<Option<String>> {
+    let output = Command::new("git").arg("config").args(args).output()?;
+    match output.status.code() {
+        Some(0) => Ok(Some(String::from_utf8(output.stdout)?.trim().to_owned())),
+        // the key is not set
+        Some(1) => Ok(None),
+        _ => Err(anyhow!(
+            "fatal: failed to read git config:\n{}",
+            String::from_utf8(output.stderr)?.trim_end()
+        )),
+    }
+}
@@ -0,0 +1,320 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use anyhow::Result;
+use clap::ValueEnum;
+
+use crate::backend::{Backend, Branch};
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Strategy {
+    /// Merging the branch into main would not change main's tree.
+    Tree,
+    /// The cumulative diff of the branch matches a single commit on main.
+    PatchId,
+    /// Every commit of the branch has an equivalent commit on main.
+    Cherry,
+    /// Any of the above.
+    All,
+}
+
+/// How a branch whose upstream is gone is treated, which usually means
+/// it was deleted after merging a PR and then pruned by `git fetch --prune`.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Gone {
+    /// Only show that the upstream is gone.
+    Ignore,
+    /// Delete the branch even if it wasn't detected as merged.
+    Sufficient,
+    /// Only delete merged branches if their upstream is gone, too.
+    Required,
+}
+
+impl Gone {
+    /// Whether a branch is to be deleted, given that it was detected as
+    /// merged or not and its upstream is gone or not.
+    pub fn should_delete(self, merged: bool, gone: bool) -> bool {
+        match self {
+            Gone::Ignore => merged,
+            Gone::Sufficient => merged || gone,
+            Gone::Required => merged && gone,
+        }
+    }
+}
+
+/// Checks if the branch had an upstream that no longer exists.
+pub fn upstream_gone(branch: &Branch) -> bool {
+    branch.upstream.is_some() && branch.upstream_track == "[gone]"
+}
+
+/// Why a branch is considered merged.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Reason {
+    /// The branch is an ancestor of main,
+    /// e.g. after a regular merge or a fast-forward.
+    Ancestor,
+    /// Every commit of the branch has a patch-equivalent commit on main.
+    Rebased,
+    /// The changes of the branch were squashed into main.
+    Squashed,
+}
+
+impl std::fmt::Display for Reason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Reason::Ancestor => "ancestor",
+            Reason::Rebased => "rebased",
+            Reason::Squashed => "squashed",
+        })
+    }
+}
+
+/// Detects which branches were merged into main or one of the other bases.
+pub struct Detector<'a> {
+    backend: &'a dyn Backend,
+    strategy: Strategy,
+    /// Main comes first.
+    bases: Vec<Base>,
+    max_depth: usize,
+    patch_id_depth: usize,
+}
+
+/// A base along with what is the same for every branch.
+struct Base {
+    name: String,
+    tree: String,
+    /// The commits of each patch-id in the base's latest history,
+    /// computed once the first branch needs them.
+    patch_ids: Mutex<Option<Arc<PatchIds>>>,
+    /// Whether a branch forked before the history covered by `patch_ids`
+    /// was already reported.
+    warned: AtomicBool,
+}
+
+/// The commits with each patch-id.
+type PatchIds = HashMap<String, Vec<String>>;
+
+impl<'a> Detector<'a> {
+    pub fn new(
+        backend: &'a dyn Backend,
+        strategy: Strategy,
+        bases: &[String],
+        max_depth: usize,
+        patch_id_depth: usize,
+    ) -> Result<Self> {
+        let bases = bases
+            .iter()
+            .map(|base| {
+                Ok(Base {
+                    name: base.clone(),
+                    tree: backend.tree_of(base)?,
+                    patch_ids: Mutex::new(None),
+                    warned: AtomicBool::new(false),
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            backend,
+            strategy,
+            bases,
+            max_depth,
+            patch_id_depth,
+        })
+    }
+
+    /// Runs [Detector::detect] for all branches on a pool of worker
+    /// threads. The results are in the same order as the branches.
+    pub fn detect_all(&self, branches: &[String]) -> Result<Vec<Option<(Reason, &str)>>> {
+        let num_workers = std::thread::available_parallelism()
+            .map_or(1, |n| n.get())
+            .min(branches.len());
+        let next_branch = AtomicUsize::new(0);
+
+        let mut results: Vec<_> = std::thread::scope(|scope| {
+            let workers: Vec<_> = (0..num_workers)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut results = Vec::new();
+                        loop {
+                            let i = next_branch.fetch_add(1, Ordering::Relaxed);
+                            let Some(branch) = branches.get(i) else {
+                                return results;
+                            };
+                            results.push((i, self.detect(branch)));
+                        }
+                    })
+                })
+                .collect();
+            workers
+                .into_iter()
+                .flat_map(|worker| worker.join().expect("detection worker panicked"))
+                .collect()
+        });
+        results.sort_by_key(|(i, _)| *i);
+        results.into_iter().map(|(_, result)| result).collect()
+    }
+
+    /// Returns how the branch was merged and into which base, if it was.
+    pub fn detect(&self, branch: &str) -> Result<Option<(Reason, &str)>> {
+        for base in &self.bases {
+            if let Some(reason) = self.detect_in(base, branch)? {
+                return Ok(Some((reason, &base.name)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Returns how the branch was merged into `base`, if it was.
+    fn detect_in(&self, base: &Base, branch: &str) -> Result<Option<Reason>> {
+        if self.backend.is_ancestor(branch, &base.name)? {
+            return Ok(Some(Reason::Ancestor));
+        }
+        let is_merged = match self.strategy {
+            Strategy::Tree => self.is_fully_merged(&base.name, &base.tree, branch)?,
+            Strategy::PatchId => self.is_squashed_by_patch_id(base, branch)?,
+            Strategy::Cherry => self.is_cherry_picked(base, branch)?,
+            Strategy::All => {
+                self.is_fully_merged(&base.name, &base.tree, branch)?
+                    || self.is_squashed_by_patch_id(base, branch)?
+                    || self.is_cherry_picked(base, branch)?
+            }
+        };
+        if !is_merged {
+            return Ok(None);
+        }
+        // The other strategies also catch rebased branches, find out which
+        // one it is. The base's patch-ids are shared by all branches, so
+        // this only diffs the commits of the branch.
+        if self.strategy == Strategy::Cherry || self.is_cherry_picked(base, branch)? {
+            Ok(Some(Reason::Rebased))
+        } else {
+            Ok(Some(Reason::Squashed))
+        }
+    }
+
+    fn is_fully_merged(&self, base: &str, base_tree: &str, branch: &str) -> Result<bool> {
+        match self.merge_is_noop(base, base_tree, branch)? {
+            Some(is_noop) => Ok(is_noop),
+            // Merging conflicts, which happens when the branch was squashed
+            // into the base and the same lines were changed again afterwards.
+            // Search the base's history for the squash commit, at most until the
+            // merge-base. Bisecting is not possible, because conflicts can
+            // appear on either side of the squash commit:
+            //
+            // * commit with conflict
+            // * actual squash commit, can merge with this
+            // * commit with conflict
+            None => {
+                let Some(merge_base) = self.backend.merge_base(base, branch)? else {
+                    return Ok(false);
+                };
+
+                let history = self.backend.first_parent_history(
+                    base,
+                    &merge_base,
+                    self.max_depth.saturating_add(1),
+                )?;
+                // the first one is the base itself
+                for (candidate, candidate_tree) in history.iter().skip(1) {
+                    if self.merge_is_noop(candidate, candidate_tree, branch)? == Some(true) {
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
+            }
+        }
+    }
+
+    /// Checks if merging `branch` into `base` would leave the tree of `base`
+    /// unchanged. Returns `None` if the merge has conflicts.
+    fn merge_is_noop(&self, base: &str, base_tree: &str, branch: &str) -> Result<Option<bool>> {
+        Ok(self
+            .backend
+            .merge_trees(base, branch)?
+            .map(|squashed_tree| squashed_tree == base_tree))
+    }
+
+    /// Checks if the changes of the branch as a whole match a commit
+    /// on the base since the merge-base. Unlike the tree comparison, this
+    /// still works if later commits on the base touched the same lines.
+    fn is_squashed_by_patch_id(&self, base: &Base, branch: &str) -> Result<bool> {
+        let Some(merge_base) = self.backend.merge_base(&base.name, branch)? else {
+            return Ok(false);
+        };
+        let Some(branch_patch_id) = self.backend.diff_patch_id(&merge_base, branch)? else {
+            // nothing on the branch that isn't on main already
+            return Ok(true);
+        };
+        self.has_patch_since(base, &merge_base, &branch_patch_id)
+    }
+
+    /// Checks if every commit of the branch has a patch-equivalent commit
+    /// on the base since the merge-base, like `git cherry` does.
+    fn is_cherry_picked(&self, base: &Base, branch: &str) -> Result<bool> {
+        let Some(merge_base) = self.backend.merge_base(&base.name, branch)? else {
+            return Ok(false);
+        };
+        for (_, patch_id) in self
+            .backend
+            .patch_ids(branch, Some(&merge_base), usize::MAX)?
+        {
+            if !self.has_patch_since(base, &merge_base, &patch_id)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Checks if a commit with the given patch-id was made on the base
+    /// since the merge-base.
+    fn has_patch_since(&self, base: &Base, merge_base: &str, patch_id: &str) -> Result<bool> {
+        let base_patch_ids = self.base_patch_ids(base)?;
+        for commit in base_patch_ids.get(patch_id).into_iter().flatten() {
+            if !self.backend.is_ancestor(commit, merge_base)? {
+                return Ok(true);
+            }
+        }
+        self.warn_if_beyond_patch_ids(base, merge_base)?;
+        Ok(false)
+    }
+
+    /// Warns once per base if the base has more commits since the
+    /// merge-base than its patch-ids cover, so a match may have been missed.
+    fn warn_if_beyond_patch_ids(&self, base: &Base, merge_base: &str) -> Result<()> {
+        if base.warned.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+        let (_, behind) = self.backend.ahead_behind(merge_base, &base.name)?;
+        if behind > self.patch_id_depth && !base.warned.swap(true, Ordering::Relaxed) {
+            eprintln!(
+                "Some branches forked from '{}' more than {} commits ago, \
+                 they may not be detected as merged or rebased. \
+                 Increase --patch-id-depth to compare against more commits.",
+                base.name, self.patch_id_depth
+            );
+        }
+        Ok(())
+    }
+
+    /// The commits of each patch-id in the base's latest history.
+    fn base_patch_ids(&self, base: &Base) -> Result<Arc<PatchIds>> {
+        // other workers wait instead of computing the same thing
+        let mut cached = base.patch_ids.lock().unwrap();
+        if let Some(patch_ids) = &*cached {
+            return Ok(patch_ids.clone());
+        }
+        let mut patch_ids = PatchIds::new();
+        for (commit, patch_id) in self
+            .backend
+            .patch_ids(&base.name, None, self.patch_id_depth)?
+        {
+            patch_ids.entry(patch_id).or_default().push(commit);
+        }
+        Ok(cached.insert(Arc::new(patch_ids)).clone())
+    }
+}
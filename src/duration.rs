@@ -0,0 +1,73 @@
+//! Durations on the command line, e.g. `--older-than 30d`.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Parses a number followed by a unit, one of
+/// `s`, `m` (minutes), `h`, `d`, `w`, `mo` (30 days) or `y` (365 days).
+pub fn parse(s: &str) -> Result<Duration, String> {
+    let unit_start = s
+        .find(|c: char| !c.is_ascii_digit())
+        .ok_or_else(|| format!("missing unit in '{s}', e.g. 30d"))?;
+    let (amount, unit) = s.split_at(unit_start);
+    let amount: u64 = amount
+        .parse()
+        .map_err(|_| format!("missing amount in '{s}', e.g. 30d"))?;
+    let seconds_per_unit = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        "mo" => 30 * 24 * 60 * 60,
+        "y" => 365 * 24 * 60 * 60,
+        _ => return Err(format!("unknown unit '{unit}', use s, m, h, d, w, mo or y")),
+    };
+    amount
+        .checked_mul(seconds_per_unit)
+        .filter(|&seconds| i64::try_from(seconds).is_ok())
+        .map(Duration::from_secs)
+        .ok_or_else(|| format!("duration '{s}' is too long"))
+}
+
+/// The seconds of a duration as a signed number, as used for timestamps.
+/// Saturates instead of wrapping.
+pub fn seconds(duration: Duration) -> i64 {
+    i64::try_from(duration.as_secs()).unwrap_or(i64::MAX)
+}
+
+/// The timestamp `duration` before `now`.
+pub fn before(now: i64, duration: Duration) -> i64 {
+    now.saturating_sub(seconds(duration))
+}
+
+/// The current time as a unix timestamp.
+pub fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64)
+}
+
+/// Formats an age in seconds for humans, e.g. "3 months".
+pub fn format_age(seconds: i64) -> String {
+    const MINUTE: i64 = 60;
+    const HOUR: i64 = 60 * MINUTE;
+    const DAY: i64 = 24 * HOUR;
+    const WEEK: i64 = 7 * DAY;
+    const MONTH: i64 = 30 * DAY;
+    const YEAR: i64 = 365 * DAY;
+
+    let (amount, unit) = match seconds.max(0) {
+        s if s < 2 * MINUTE => (s, "second"),
+        s if s < 2 * HOUR => (s / MINUTE, "minute"),
+        s if s < 2 * DAY => (s / HOUR, "hour"),
+        s if s < 2 * WEEK => (s / DAY, "day"),
+        s if s < 2 * MONTH => (s / WEEK, "week"),
+        s if s < 2 * YEAR => (s / MONTH, "month"),
+        s => (s / YEAR, "year"),
+    };
+    if amount == 1 {
+        format!("{amount} {unit}")
+    } else {
+        format!("{amount} {unit}s")
+    }
+}
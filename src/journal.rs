@@ -0,0 +1,223 @@
+//! A log of deleted branches, so they can be restored with `git qp undo`.
+//!
+//! Each line of `<git-common-dir>/quickprune/log` records one deleted
+//! branch as tab-separated fields:
+//! run id, unix timestamp, branch, tip commit, remote (empty if the
+//! remote branch was kept), `remote-only` if there was no local branch
+//! to delete (empty otherwise, or missing in older logs).
+//!
+//! Undo restores the local branch unless only the remote branch was
+//! deleted with `--remote-only`, and the remote branch if it was deleted.
+
+use std::{
+    fs::OpenOptions,
+    io::Write,
+    path::PathBuf,
+    process::Command,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, Result};
+
+/// Namespace of the refs keeping deleted commits from being collected.
+static TRASH_REFS: &str = "refs/quickprune/trash";
+
+/// Marks entries of branches that were only deleted on the remote.
+static REMOTE_ONLY: &str = "remote-only";
+
+struct Entry {
+    run: String,
+    branch: String,
+    commit: String,
+    remote: Option<String>,
+    /// Only the remote branch was deleted, with `--remote-only`.
+    remote_only: bool,
+}
+
+impl Entry {
+    fn parse(line: &str) -> Option<Self> {
+        let mut fields = line.split('\t');
+        let run = fields.next()?.to_owned();
+        let _timestamp = fields.next()?;
+        Some(Self {
+            run,
+            branch: fields.next()?.to_owned(),
+            commit: fields.next()?.to_owned(),
+            remote: fields.next().filter(|r| !r.is_empty()).map(str::to_owned),
+            remote_only: fields.next() == Some(REMOTE_ONLY),
+        })
+    }
+}
+
+pub struct Journal {
+    path: PathBuf,
+    run: String,
+    backup_refs: bool,
+}
+
+impl Journal {
+    pub fn open(backup_refs: bool) -> Result<Self> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        Ok(Self {
+            path: log_path()?,
+            run: format!("{now}-{}", std::process::id()),
+            backup_refs,
+        })
+    }
+
+    /// Creates a backup ref for a branch that is about to be deleted,
+    /// if enabled.
+    pub fn backup(&self, branch: &str, commit: &str) -> Result<()> {
+        if !self.backup_refs {
+            return Ok(());
+        }
+        let output = Command::new("git")
+            .args(["update-ref", &format!("{TRASH_REFS}/{branch}"), commit])
+            .output()?;
+        if !output.status.success() {
+            return Err(anyhow!(
+                "failed to create backup ref for '{branch}':\n{}",
+                String::from_utf8(output.stderr)?.trim_end()
+            ));
+        }
+        Ok(())
+    }
+
+    /// Removes the backup ref again, e.g. because deleting the branch failed.
+    pub fn drop_backup(&self, branch: &str) -> Result<()> {
+        if self.backup_refs {
+            delete_backup_ref(branch)?;
+        }
+        Ok(())
+    }
+
+    /// Records a deleted local branch, along with the remote
+    /// it was deleted from, too.
+    pub fn record(&self, branch: &str, commit: &str, remote: Option<&str>) -> Result<()> {
+        self.append(branch, commit, remote.unwrap_or_default(), "")
+    }
+
+    /// Records a branch deleted from the remote with `--remote-only`.
+    pub fn record_remote_only(&self, branch: &str, commit: &str, remote: &str) -> Result<()> {
+        self.append(branch, commit, remote, REMOTE_ONLY)
+    }
+
+    fn append(&self, branch: &str, commit: &str, remote: &str, remote_only: &str) -> Result<()> {
+        std::fs::create_dir_all(self.path.parent().unwrap())?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        writeln!(
+            file,
+            "{}\t{timestamp}\t{branch}\t{commit}\t{remote}\t{remote_only}",
+            self.run,
+        )?;
+        Ok(())
+    }
+}
+
+fn delete_backup_ref(branch: &str) -> Result<()> {
+    Command::new("git")
+        .args(["update-ref", "-d", &format!("{TRASH_REFS}/{branch}")])
+        .output()?;
+    Ok(())
+}
+
+fn log_path() -> Result<PathBuf> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--git-common-dir"])
+        .output()?;
+    if !output.status.success() {
+        return Err(anyhow!("{}", String::from_utf8(output.stderr)?.trim_end()));
+    }
+    let git_dir = PathBuf::from(String::from_utf8(output.stdout)?.trim());
+    Ok(git_dir.join("quickprune").join("log"))
+}
+
+fn read_entries() -> Result<Vec<Entry>> {
+    let content = match std::fs::read_to_string(log_path()?) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e.into()),
+    };
+    Ok(content.lines().filter_map(Entry::parse).collect())
+}
+
+/// Restores the most recently deleted branch with the given name,
+/// or all branches deleted in the last run.
+pub fn undo(branch: Option<&str>) -> Result<()> {
+    let entries = read_entries()?;
+    let to_restore: Vec<_> = match branch {
+        Some(branch) => entries
+            .iter()
+            .rev()
+            .find(|entry| entry.branch == branch)
+            .into_iter()
+            .collect(),
+        None => {
+            let last_run = entries.last().map(|entry| &entry.run);
+            entries
+                .iter()
+                .filter(|entry| Some(&entry.run) == last_run)
+                .collect()
+        }
+    };
+    if to_restore.is_empty() {
+        return Err(match branch {
+            Some(branch) => anyhow!("fatal: no deleted branch '{branch}' found in the log"),
+            None => anyhow!("fatal: nothing to undo"),
+        });
+    }
+
+    for entry in to_restore {
+        restore(entry)?;
+    }
+    Ok(())
+}
+
+fn restore(entry: &Entry) -> Result<()> {
+    let Entry { branch, commit, .. } = entry;
+    let mut restored = true;
+    if !entry.remote_only {
+        let output = Command::new("git")
+            .args(["branch", branch, commit])
+            .output()?;
+        if output.status.success() {
+            println!(
+                "Restored branch '{branch}' at {}",
+                &commit[..commit.len().min(7)]
+            );
+        } else {
+            print!(
+                "Failed to restore branch '{branch}':\n{}",
+                String::from_utf8(output.stderr)?
+            );
+            restored = false;
+        }
+    }
+
+    // even if restoring the local branch failed, e.g. because it was
+    // recreated meanwhile
+    if let Some(remote) = &entry.remote {
+        let output = Command::new("git")
+            .args(["push", remote, &format!("{commit}:refs/heads/{branch}")])
+            .output()?;
+        if output.status.success() {
+            println!("Restored remote branch '{remote}/{branch}'");
+        } else {
+            print!(
+                "Failed to restore remote branch '{remote}/{branch}':\n{}",
+                String::from_utf8(output.stderr)?
+            );
+            restored = false;
+        }
+    }
+
+    if !restored {
+        return Ok(());
+    }
+    // the backup is not needed anymore, if there is one
+    delete_backup_ref(branch)
+}
@@ -1,190 +1,730 @@
-use std::{path::PathBuf, process::Command};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    path::PathBuf,
+    process::Command,
+    time::Duration,
+};
 
 use anyhow::{anyhow, Result};
-use clap::Parser;
+use clap::{Args, Parser, Subcommand};
+
+mod backend;
+mod config;
+mod detect;
+mod duration;
+mod journal;
+mod pattern;
+mod staging;
+mod worktree;
+
+use backend::{Backend, Branch};
+use config::Config;
+use detect::{Detector, Gone, Strategy};
+use journal::Journal;
+use pattern::Pattern;
+use staging::{Action, Entry, FOOTER};
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Commands>,
+
     /// Specify main branch, e.g. master or trunk.
-    #[arg(short, long, default_value = "main")]
-    main_branch: String,
+    /// Detected from the remote's HEAD by default.
+    /// [config: quickprune.mainBranch]
+    #[arg(short, long)]
+    main_branch: Option<String>,
 
     /// Useful for managing forks, when merging a PR may not
     /// delete the branch on your fork.
-    #[arg(short = 'r', long)]
+    /// [config: quickprune.deleteRemote]
+    #[arg(short = 'r', long, overrides_with = "keep_remote_branches")]
     also_delete_remote_branches: bool,
 
+    /// Don't delete remote branches, even if configured to.
+    #[arg(long, overrides_with = "also_delete_remote_branches")]
+    keep_remote_branches: bool,
+
     /// By default, the editor is only opened if there is at least
     /// one branch to delete.
     #[arg(short = 'e', long)]
     always_open_editor: bool,
 
-    #[arg(long, default_value = "origin")]
-    remote: String,
+    /// Delete the detected branches without opening the editor.
+    #[arg(short, long, conflicts_with_all = ["always_open_editor", "dry_run"])]
+    yes: bool,
+
+    /// Print the branches that would be deleted without deleting them.
+    /// Exits with status 3 if there are any, unlike usage errors (2)
+    /// and other errors (1).
+    #[arg(short = 'n', long, conflicts_with = "always_open_editor")]
+    dry_run: bool,
+
+    /// Also treat branches merged into this remote branch as merged, e.g.
+    /// 'release/*'. Can be repeated. [config: quickprune.base]
+    #[arg(long, value_name = "GLOB")]
+    base: Vec<String>,
+
+    /// The remote main is compared against and branches are deleted from,
+    /// unless --upstream or --push-remote say otherwise.
+    /// [default: origin] [config: quickprune.remote]
+    #[arg(long)]
+    remote: Option<String>,
+
+    /// The remote main is compared against, e.g. 'upstream' when working
+    /// on a fork. [default: --remote] [config: quickprune.upstream]
+    #[arg(long, value_name = "REMOTE")]
+    upstream: Option<String>,
+
+    /// The remote branches are deleted from. By default, the push remote
+    /// of each branch (branch.<name>.pushRemote, remote.pushDefault),
+    /// falling back to --remote.
+    #[arg(long, value_name = "REMOTE")]
+    push_remote: Option<String>,
+
+    /// Prune the branches on the push remote instead of local ones, e.g. on
+    /// a fork after a fresh clone. The push remote is --push-remote,
+    /// remote.pushDefault or --remote.
+    #[arg(long, conflicts_with_all = ["also_delete_remote_branches", "keep_remote_branches"])]
+    remote_only: bool,
+
+    /// When merging a branch conflicts with main, how many commits of
+    /// main's history to search for the one the branch was squashed into.
+    #[arg(long, default_value_t = 100)]
+    max_depth: usize,
+
+    /// How many commits of main's history the patch-id and cherry
+    /// strategies compare branches against, as well as the check that
+    /// labels merged branches as rebased.
+    #[arg(long, value_name = "N", default_value_t = 1000)]
+    patch_id_depth: usize,
+
+    /// How to detect that a branch was merged.
+    #[arg(long, value_enum, default_value_t = Strategy::Tree)]
+    strategy: Strategy,
+
+    /// How to treat branches whose upstream is gone, e.g. after
+    /// `git fetch --prune` removed a merged PR's branch.
+    #[arg(long, value_enum, default_value_t = Gone::Ignore)]
+    gone: Gone,
+
+    /// Never delete branches matching this glob, e.g. 'release/*'.
+    /// Can be repeated. [config: quickprune.protect]
+    #[arg(long, value_name = "GLOB")]
+    protect: Vec<String>,
+
+    /// Don't protect the branches in the default list:
+    /// main, master, trunk, develop, gh-pages and release/*
+    #[arg(long)]
+    no_default_protect: bool,
+
+    /// Only consider branches matching this glob, or regex if prefixed
+    /// with 're:'. Can be repeated to match any of the patterns.
+    #[arg(long, value_name = "PATTERN")]
+    only: Vec<Pattern>,
+
+    /// Skip branches matching this glob, or regex if prefixed with 're:'.
+    /// Can be repeated.
+    #[arg(long, value_name = "PATTERN")]
+    exclude: Vec<Pattern>,
+
+    /// Only consider branches whose tip was committed longer ago
+    /// than this, e.g. 30d. Units: s, m, h, d, w, mo, y.
+    #[arg(long, value_name = "DURATION", value_parser = duration::parse)]
+    older_than: Option<Duration>,
+
+    /// Only consider branches whose tip was committed more recently
+    /// than this, e.g. 2w. Units: s, m, h, d, w, mo, y.
+    #[arg(long, value_name = "DURATION", value_parser = duration::parse)]
+    newer_than: Option<Duration>,
+
+    /// Only consider branches whose tip's author name or email matches
+    /// this glob, or regex if prefixed with 're:'.
+    #[arg(long, value_name = "PATTERN")]
+    author: Option<Pattern>,
+
+    /// Also list unmerged branches whose tip is older than this, e.g. 3mo,
+    /// in a separate commented section. Units: s, m, h, d, w, mo, y.
+    #[arg(long, value_name = "DURATION", value_parser = duration::parse)]
+    stale: Option<Duration>,
+
+    /// Keep a ref under refs/quickprune/trash/ for every deleted branch,
+    /// so its commits are not garbage collected before an undo.
+    #[arg(long)]
+    backup_refs: bool,
+}
+
+#[derive(Subcommand, Debug)]
+enum Commands {
+    /// Restore branches deleted by a previous run.
+    Undo(UndoArgs),
+}
+
+#[derive(Args, Debug)]
+struct UndoArgs {
+    /// The branch to restore.
+    #[arg(required_unless_present = "last_run")]
+    branch: Option<String>,
+
+    /// Restore all branches deleted by the last run.
+    #[arg(long, conflicts_with = "branch")]
+    last_run: bool,
 }
 
 fn main() -> Result<()> {
     let cli_args = Cli::parse();
-    let main = &cli_args.main_branch;
-    let remote = &cli_args.remote;
-
-    ensure_main_branch_exists(remote, main)?;
+    if let Some(Commands::Undo(undo_args)) = &cli_args.command {
+        return journal::undo(undo_args.branch.as_deref());
+    }
+    let backend = backend::open()?;
+    let backend = backend.as_ref();
+
+    let config = Config::load()?;
+    let remote = &cli_args
+        .remote
+        .clone()
+        .or(config.remote)
+        .unwrap_or_else(|| "origin".into());
+    let upstream = &cli_args
+        .upstream
+        .clone()
+        .or(config.upstream)
+        .unwrap_or_else(|| remote.clone());
+    let push_remote_of = |branch: &str| {
+        cli_args
+            .push_remote
+            .as_deref()
+            .or(config.push_remotes.get(branch).map(String::as_str))
+            .or(config.push_default.as_deref())
+            .unwrap_or(remote)
+    };
+    // the remote whose branches are pruned in --remote-only mode
+    let remote_only = cli_args.remote_only.then(|| {
+        cli_args
+            .push_remote
+            .as_deref()
+            .or(config.push_default.as_deref())
+            .unwrap_or(remote)
+    });
+    let also_delete_remote_branches = if cli_args.also_delete_remote_branches {
+        true
+    } else if cli_args.keep_remote_branches {
+        false
+    } else {
+        config.delete_remote.unwrap_or_default()
+    };
+
+    let mut protected = cli_args.protect.clone();
+    protected.extend(config.protect);
+    if !cli_args.no_default_protect {
+        protected.extend(DEFAULT_PROTECTED.iter().map(|&glob| glob.to_owned()));
+    }
+    let is_protected = |branch: &str| {
+        protected
+            .iter()
+            .any(|glob| pattern::glob_match(glob, branch))
+    };
+
+    let main = &match cli_args.main_branch.clone().or(config.main_branch) {
+        Some(main) => {
+            ensure_main_branch_exists(backend, upstream, &main)?;
+            main
+        }
+        None => {
+            let main = detect_main_branch(backend, upstream)?;
+            eprintln!("Comparing against '{upstream}/{main}'.");
+            main
+        }
+    };
 
     let mut branches_to_delete = String::new();
     let mut branches_to_keep = String::new();
-
-    // can be empty, e.g. in detached HEAD state
-    let current_branch = get_current_branch()?;
-
-    for branch in get_local_branches()? {
-        if &branch == main || branch == current_branch {
-            continue;
+    let mut stale_branches = String::new();
+    let mut checked_out_branches = String::new();
+
+    // can be empty, e.g. in detached HEAD state,
+    // and doesn't matter for remote branches
+    let current_branch = match remote_only {
+        Some(_) => String::new(),
+        None => backend.current_branch()?,
+    };
+    // branches checked out in other worktrees
+    let worktrees: HashMap<_, _> = match remote_only {
+        Some(_) => HashMap::new(),
+        None => worktree::list()?
+            .into_iter()
+            .filter_map(|worktree| Some((worktree.branch.clone()?, worktree)))
+            .filter(|(branch, _)| *branch != current_branch)
+            .collect(),
+    };
+
+    // main comes first, so a branch merged into several bases is
+    // attributed to main
+    let mut bases = vec![main.clone()];
+    let base_globs: Vec<_> = cli_args.base.iter().chain(&config.base).collect();
+    if !base_globs.is_empty() {
+        let remote_branches = list_remote_branches(upstream)?;
+        for glob in base_globs {
+            let matching: Vec<_> = remote_branches
+                .iter()
+                .map(|branch| &branch.name)
+                .filter(|branch| pattern::glob_match(glob, branch))
+                .collect();
+            if matching.is_empty() {
+                eprintln!("No branch on '{upstream}' matches --base '{glob}'.");
+            }
+            for branch in matching {
+                if !bases.contains(branch) {
+                    bases.push(branch.clone());
+                }
+            }
         }
+    }
 
+    let branches = match remote_only {
+        Some(prune_remote) => {
+            eprintln!("Pruning branches on '{prune_remote}'.");
+            list_remote_branches(prune_remote)?
+        }
+        None => backend.local_branches()?,
+    };
+    let branches: Vec<_> = branches
+        .into_iter()
+        .filter(|branch| !bases.contains(&branch.name) && branch.name != current_branch)
+        .filter(|branch| !is_protected(&branch.name))
+        .collect();
+    let branches = apply_filters(branches, &cli_args);
+    let remote_main = format!("{upstream}/{main}");
+    let remote_bases: Vec<_> = bases
+        .iter()
+        .map(|base| format!("{upstream}/{base}"))
+        .collect();
+    let detector = Detector::new(
+        backend,
+        cli_args.strategy,
+        &remote_bases,
+        cli_args.max_depth,
+        cli_args.patch_id_depth,
+    )?;
+    // detect on the tips the filters saw, even if a branch moves meanwhile
+    let tips: Vec<_> = branches
+        .iter()
+        .map(|branch| branch.commit.clone())
+        .collect();
+    let detected = detector.detect_all(&tips)?;
+
+    let now = duration::now();
+    let stale_cutoff = cli_args.stale.map(|stale| duration::before(now, stale));
+
+    // align the comments
+    let width = branches
+        .iter()
+        .map(|branch| branch.name.len())
+        .max()
+        .unwrap_or_default();
+
+    let mut merged = HashSet::new();
+    for (branch, detected) in branches.iter().zip(detected) {
         use std::fmt::Write;
 
-        if is_fully_merged(&format!("{remote}/{main}"), &branch)? {
-            writeln!(branches_to_delete, "{}", branch)?;
-        } else {
-            writeln!(branches_to_keep, "# {}", branch)?;
+        let name = &branch.name;
+        let worktree = worktrees.get(name).map(|worktree| &worktree.path);
+        let details = describe(branch, worktree, now);
+        let subject = &branch.subject;
+        let gone = detect::upstream_gone(branch);
+        let mut reasons: Vec<_> = detected
+            .map(|(reason, base)| {
+                if base == remote_main {
+                    reason.to_string()
+                } else {
+                    format!("{reason} into {base}")
+                }
+            })
+            .into_iter()
+            .collect();
+        if gone {
+            reasons.push("upstream gone".into());
+        }
+        let reason = cli_args
+            .gone
+            .should_delete(detected.is_some(), gone)
+            .then(|| reasons.join(", "));
+        if reason.is_some() {
+            merged.insert(name.as_str());
+        }
+        match reason {
+            // deleting it fails unless the worktree is removed, so let the
+            // user opt in with the remove-worktree action
+            Some(reason) if worktree.is_some() => writeln!(
+                checked_out_branches,
+                "# {name:width$} # {reason}, {details}: {subject}",
+            )?,
+            Some(reason) => writeln!(
+                branches_to_delete,
+                "{name:0$} # {reason}, {details}: {subject}",
+                width + 2
+            )?,
+            None if stale_cutoff.is_some_and(|cutoff| branch.committer_date < cutoff) => {
+                let (ahead, behind) = backend.ahead_behind(&branch.commit, &remote_main)?;
+                writeln!(
+                    stale_branches,
+                    "# {name:width$} # {details}, {ahead} ahead, {behind} behind {remote_main}: {subject}",
+                )?;
+            }
+            None => writeln!(branches_to_keep, "# {name:width$} # {details}: {subject}")?,
         }
     }
-
-    if branches_to_delete.is_empty() && !cli_args.always_open_editor {
-        println!("Nothing to do. Use -e to force-open the editor.");
-        return Ok(());
+    if let Some(stale) = cli_args.stale.filter(|_| !stale_branches.is_empty()) {
+        let header = format!(
+            "\n# Not merged, but no new commits for {}:\n",
+            duration::format_age(duration::seconds(stale))
+        );
+        stale_branches.insert_str(0, &header);
+    }
+    if !checked_out_branches.is_empty() {
+        checked_out_branches.insert_str(0, "\n# Merged, but checked out in a worktree:\n");
     }
 
-    let staging_file_content = format!("{}{}{}", branches_to_delete, branches_to_keep, FOOTER);
-
-    let dir = tempfile::tempdir()?;
-    let staging_file_path = dir.path().join("quickprune-stage");
+    let non_interactive = cli_args.yes || cli_args.dry_run;
 
-    write_to_staging_file(&staging_file_path, staging_file_content)?;
+    if branches_to_delete.is_empty() {
+        if non_interactive {
+            println!("Nothing to do.");
+            return Ok(());
+        }
+        if stale_branches.is_empty()
+            && checked_out_branches.is_empty()
+            && !cli_args.always_open_editor
+        {
+            println!("Nothing to do. Use -e to force-open the editor.");
+            return Ok(());
+        }
+    }
 
-    // # give the user a chance to edit the list
-    Command::new(select_editor())
-        .arg(&staging_file_path)
-        .status()?;
+    let staging_file_content = format!(
+        "{}{}{}{}{}",
+        branches_to_delete, branches_to_keep, checked_out_branches, stale_branches, FOOTER
+    );
+
+    let final_user_selection = if non_interactive {
+        staging_file_content
+    } else {
+        let dir = tempfile::tempdir()?;
+        let staging_file_path = dir.path().join("quickprune-stage");
+        let rules = |local_branches| staging::Rules {
+            candidates: branches.iter().map(|branch| branch.name.as_str()).collect(),
+            local_branches,
+            current_branch: &current_branch,
+            is_protected: &is_protected,
+            only_delete: remote_only.is_some(),
+            merged: merged.clone(),
+            worktrees: &worktrees,
+            is_dirty: &worktree::is_dirty,
+        };
+
+        let mut content = staging_file_content;
+        let mut annotation = String::new();
+        let mut rejected: Option<String> = None;
+        loop {
+            write_to_staging_file(&staging_file_path, format!("{annotation}{content}"))?;
+
+            // # give the user a chance to edit the list
+            let status = Command::new(select_editor())
+                .arg(&staging_file_path)
+                .status()?;
+            if !status.success() {
+                return Err(anyhow!(
+                    "fatal: the editor exited with {status}, no branches were deleted"
+                ));
+            }
 
-    let final_user_selection = std::fs::read_to_string(&staging_file_path)?;
-    let branches_to_delete = final_user_selection
-        .lines()
-        .filter(|line| !line.is_empty() && !line.starts_with('#'))
-        .map(str::trim);
+            let edited = std::fs::read_to_string(&staging_file_path)?;
+            if edited.trim().is_empty() {
+                println!("The staging file is empty, no branches were deleted.");
+                return Ok(());
+            }
+            let edited = edited.strip_prefix(&annotation).unwrap_or(&edited);
+            let local_branches = match remote_only {
+                Some(prune_remote) => list_remote_branches(prune_remote)?,
+                None => backend.local_branches()?,
+            }
+            .into_iter()
+            .map(|branch| branch.name)
+            .collect();
+            let problems =
+                staging::validate(edited, also_delete_remote_branches, &rules(local_branches));
+            if problems.is_empty() {
+                break edited.to_owned();
+            }
+            if rejected.as_deref() == Some(edited) {
+                println!("Aborted, the staging file still has problems. No branches were deleted.");
+                return Ok(());
+            }
+            annotation = staging::annotate(&problems);
+            content = edited.to_owned();
+            rejected = Some(content.clone());
+        }
+    };
+    // validated above, or generated without problems
+    let (entries, _) = staging::parse(&final_user_selection, also_delete_remote_branches);
+    let entries: Vec<_> = entries
+        .into_iter()
+        .filter(|entry| entry.action != Action::Keep)
+        .collect();
+
+    if let Some(prune_remote) = remote_only {
+        return prune_remote_branches(backend, prune_remote, &entries, &cli_args);
+    }
 
-    if cli_args.also_delete_remote_branches {
-        for branch in branches_to_delete.clone() {
-            let mut remote_delete_handles = Vec::new();
-            if should_delete_remote_branch(remote, branch)? {
-                let child_handle = Command::new("git")
-                    .args(["push", "--delete", remote, branch])
-                    .spawn()?;
-                remote_delete_handles.push(child_handle);
+    if cli_args.dry_run {
+        for Entry { branch, action, .. } in &entries {
+            match action {
+                Action::Rename(new_name) => {
+                    println!("Would rename branch '{branch}' to '{new_name}'");
+                    continue;
+                }
+                Action::Archive => {
+                    println!("Would archive branch '{branch}' as tag 'archive/{branch}'")
+                }
+                _ => println!("Would delete branch '{branch}'"),
             }
-            for mut child_handle in remote_delete_handles {
-                child_handle.wait()?;
+            if *action == (Action::Delete { remote: true }) {
+                let remote = push_remote_of(branch);
+                match check_remote_branch(backend, remote, branch)? {
+                    RemoteBranch::Missing => {}
+                    RemoteBranch::Diverged => {
+                        println!("Would keep remote branch '{remote}/{branch}', it differs from the local one")
+                    }
+                    RemoteBranch::UpToDate(_) => {
+                        println!("Would delete remote branch '{remote}/{branch}'")
+                    }
+                }
             }
         }
+        std::process::exit(DRY_RUN_EXIT_CODE);
     }
 
-    for branch in branches_to_delete {
-        let output = Command::new("git")
-            .args(["branch", "--delete", "--force", branch])
-            .output()?;
-        if output.status.success() {
-            println!("Deleted branch '{branch}'");
-        } else {
-            print!(
-                "Failed to delete branch '{branch}':\n{}",
-                String::from_utf8(output.stderr)?
-            );
+    // one push per remote
+    let mut remote_branches_to_delete = BTreeMap::<_, Vec<_>>::new();
+    for entry in &entries {
+        if entry.action != (Action::Delete { remote: true }) {
+            continue;
+        }
+        let branch = entry.branch;
+        let remote = push_remote_of(branch);
+        match check_remote_branch(backend, remote, branch)? {
+            RemoteBranch::Missing => {}
+            RemoteBranch::Diverged => println!(
+                "Not deleting remote branch '{remote}/{branch}', it differs from the local one"
+            ),
+            RemoteBranch::UpToDate(expected_rev) => remote_branches_to_delete
+                .entry(remote)
+                .or_default()
+                .push((branch, expected_rev)),
+        }
+    }
+    let mut deleted_remote_branches = Vec::new();
+    for (remote, branches) in remote_branches_to_delete {
+        deleted_remote_branches.extend(delete_remote_branches(remote, &branches)?);
+    }
+
+    let journal = Journal::open(cli_args.backup_refs)?;
+    for Entry { branch, action, .. } in entries {
+        if let Action::Rename(new_name) = action {
+            match rename_branch(branch, new_name) {
+                Ok(()) => println!("Renamed branch '{branch}' to '{new_name}'"),
+                Err(e) => println!("Failed to rename branch '{branch}':\n{e}"),
+            }
+            continue;
+        }
+        if action == Action::RemoveWorktree {
+            let path = &worktrees[branch].path;
+            if let Err(e) = worktree::remove(path) {
+                println!("Failed to remove worktree '{path}':\n{e}");
+                continue;
+            }
+            println!("Removed worktree '{path}'");
+        }
+        let commit = backend.resolve(&format!("refs/heads/{branch}"))?;
+        if let Some(commit) = &commit {
+            if action == Action::Archive {
+                if let Err(e) = archive_branch(branch, commit) {
+                    println!("Failed to archive branch '{branch}':\n{e}");
+                    continue;
+                }
+                println!("Archived branch '{branch}' as tag 'archive/{branch}'");
+            }
+            // before deleting, so the commits are never unreferenced
+            if let Err(e) = journal.backup(branch, commit) {
+                println!("Failed to delete branch '{branch}':\n{e}");
+                continue;
+            }
+        }
+        if let Err(e) = backend.delete_branch(branch) {
+            println!("Failed to delete branch '{branch}':\n{e}");
+            journal.drop_backup(branch)?;
+            continue;
+        }
+        println!("Deleted branch '{branch}'");
+        if let Some(commit) = &commit {
+            let remote = deleted_remote_branches
+                .contains(&branch)
+                .then(|| push_remote_of(branch));
+            if let Err(e) = journal.record(branch, commit, remote) {
+                println!("Failed to log deleted branch '{branch}', undo won't find it:\n{e}");
+            }
         }
     }
 
     Ok(())
 }
 
-fn ensure_main_branch_exists(remote: &str, main: &str) -> Result<()> {
-    if !Command::new("git")
-        .args(["rev-parse", &format!("{remote}/{main}")])
-        .output()?
-        .status
-        .success()
-    {
-        return Err(anyhow!("fatal: main branch '{main}' not found"));
+/// Filters branches by --only, --exclude, --older-than, --newer-than
+/// and --author and reports how many branches each of them skipped.
+fn apply_filters(mut branches: Vec<Branch>, cli_args: &Cli) -> Vec<Branch> {
+    let mut retain = |skipped: &str, keep: &dyn Fn(&Branch) -> bool| {
+        let num_branches = branches.len();
+        branches.retain(keep);
+        eprintln!(
+            "Skipped {} branches {skipped}.",
+            num_branches - branches.len()
+        );
+    };
+
+    if !cli_args.only.is_empty() {
+        retain("not matching --only", &|branch| {
+            cli_args.only.iter().any(|p| p.matches(&branch.name))
+        });
     }
-    Ok(())
+    if !cli_args.exclude.is_empty() {
+        retain("matching --exclude", &|branch| {
+            !cli_args.exclude.iter().any(|p| p.matches(&branch.name))
+        });
+    }
+    let now = duration::now();
+    if let Some(older_than) = cli_args.older_than {
+        let cutoff = duration::before(now, older_than);
+        retain("newer than --older-than", &|branch| {
+            branch.committer_date < cutoff
+        });
+    }
+    if let Some(newer_than) = cli_args.newer_than {
+        let cutoff = duration::before(now, newer_than);
+        retain("older than --newer-than", &|branch| {
+            branch.committer_date > cutoff
+        });
+    }
+    if let Some(author) = &cli_args.author {
+        retain("not matching --author", &|branch| {
+            author.matches(&branch.author_name) || author.matches(&branch.author_email)
+        });
+    }
+    branches
 }
 
-fn get_current_branch() -> Result<String> {
-    let mut git_output = Command::new("git")
-        .args(["branch", "--show-current"])
-        .output()?
-        .stdout;
-    git_output.pop();
-    Ok(String::from_utf8(git_output)?)
+fn ensure_main_branch_exists(backend: &dyn Backend, remote: &str, main: &str) -> Result<()> {
+    if backend.resolve(&format!("{remote}/{main}"))?.is_none() {
+        return Err(anyhow!("fatal: main branch '{main}' not found"));
+    }
+    Ok(())
 }
 
-fn get_local_branches() -> Result<Vec<String>> {
-    let git_output = Command::new("git")
-        .args(["branch", "--format", "%(refname:short)"])
-        .output()?
-        .stdout;
-    Ok(String::from_utf8(git_output)?
+/// The branches on the remote, named without the remote's prefix.
+fn list_remote_branches(remote: &str) -> Result<Vec<Branch>> {
+    let output = Command::new("git")
+        .args([
+            "for-each-ref",
+            "--format=%(refname:lstrip=3)%09%(objectname)%09%(committerdate:unix)%09%(authorname)%09%(authoremail:trim)%09%(contents:subject)",
+            &format!("refs/remotes/{remote}/"),
+        ])
+        .output()?;
+    Ok(String::from_utf8(output.stdout)?
         .lines()
-        .map(|l| l.to_owned())
+        .filter_map(|line| {
+            let mut fields = line.splitn(6, '\t');
+            Some(Branch {
+                name: fields.next()?.to_owned(),
+                commit: fields.next()?.to_owned(),
+                committer_date: fields.next()?.parse().ok()?,
+                author_name: fields.next()?.to_owned(),
+                author_email: fields.next()?.to_owned(),
+                upstream: None,
+                upstream_track: String::new(),
+                subject: fields.next().unwrap_or_default().to_owned(),
+            })
+        })
+        .filter(|branch| branch.name != "HEAD")
         .collect())
 }
 
-fn is_fully_merged(remote_main: &str, branch: &str) -> Result<bool> {
-    let merge_tree_output = Command::new("git")
-        .args(["merge-tree", remote_main, branch])
+/// Common names of main branches, tried in order if nothing else
+/// points to the main branch.
+static COMMON_MAIN_BRANCHES: &[&str] = &["main", "master", "trunk", "develop"];
+
+fn detect_main_branch(backend: &dyn Backend, remote: &str) -> Result<String> {
+    let remote_head = Command::new("git")
+        .args([
+            "symbolic-ref",
+            "--quiet",
+            "--short",
+            &format!("refs/remotes/{remote}/HEAD"),
+        ])
         .output()?;
-    if !merge_tree_output.status.success() {
-        // merge-tree reported conflict.
-        // TODO attempt to merge with predecessors of remote_main,
-        // at most until merge-base.
-        // binary-search the latest commit that doesn't cause a conflict.
-        // maybe binary seach is not possible, because this is:
-        //
-        // * commit with conflict
-        // * actual squash commit, can merge with this
-        // * commit with conflict
-        //
-        // so that means we can't use a merge conflict as evidence
-        // that all later commits cannot be merged with.
-        // however, we might be able to say:
-        // if a commit doesn't cause a conflict, but the merge actually
-        // results in a diff, then all earier commits will surely not
-        // work either. (TODO try to find counter example)
-        return Ok(false);
-    }
-    let squashed_tree_hash = String::from_utf8(merge_tree_output.stdout)?
-        .trim()
-        .to_owned();
-
-    let cat_file_output = Command::new("git")
-        .args(["cat-file", "-p", remote_main])
+    if remote_head.status.success() {
+        let remote_head = String::from_utf8(remote_head.stdout)?;
+        if let Some(main) = remote_head.trim().strip_prefix(&format!("{remote}/")) {
+            return Ok(main.to_owned());
+        }
+    }
+
+    let default_branch = Command::new("git")
+        .args(["config", "init.defaultBranch"])
         .output()?;
-    let main_tree_hash = String::from_utf8(cat_file_output.stdout)?
-        .lines()
-        .next()
-        .unwrap_or_default()
-        .trim_start_matches("tree ")
-        .to_owned();
+    let default_branch = String::from_utf8(default_branch.stdout)?;
+    let default_branch = default_branch.trim();
+
+    std::iter::once(default_branch)
+        .chain(COMMON_MAIN_BRANCHES.iter().copied())
+        .filter(|main| !main.is_empty())
+        .find(|main| ensure_main_branch_exists(backend, remote, main).is_ok())
+        .map(str::to_owned)
+        .ok_or_else(|| {
+            anyhow!("fatal: could not detect main branch, specify it with --main-branch")
+        })
+}
 
-    Ok(squashed_tree_hash == main_tree_hash)
+/// Summarizes the tip, upstream and worktree of a branch for the staging
+/// file, e.g. "a1b2c3d, 3 days old, origin/feature [gone]".
+fn describe(branch: &Branch, worktree: Option<&String>, now: i64) -> String {
+    let short_sha = &branch.commit[..branch.commit.len().min(7)];
+    let age = duration::format_age(now - branch.committer_date);
+    let mut description = format!("{short_sha}, {age} old");
+    if let Some(upstream) = &branch.upstream {
+        description.push_str(&format!(", {upstream}"));
+        if !branch.upstream_track.is_empty() {
+            description.push_str(&format!(" {}", branch.upstream_track));
+        }
+    }
+    if let Some(path) = worktree {
+        description.push_str(&format!(", checked out in {path}"));
+    }
+    description
 }
 
-static FOOTER: &str = "
-# ^^^^^^^^^^^^^^^^^^^^^^^
-# ! TO BE FORCE-DELETED !
-#
-# Review and edit the above list of branches to be force-deleted.
-# To preserve a branch, remove it from the list or comment it.
-# To delete a commented branch, uncomment it.";
+/// Branches that are protected unless --no-default-protect is passed.
+static DEFAULT_PROTECTED: &[&str] = &[
+    "main",
+    "master",
+    "trunk",
+    "develop",
+    "gh-pages",
+    "release/*",
+];
+
+/// Exit code of a dry run that found branches to delete,
+/// distinct from the ones of errors (1) and usage errors (2).
+const DRY_RUN_EXIT_CODE: i32 = 3;
 
 fn write_to_staging_file(path: &PathBuf, content: String) -> Result<()> {
     use std::io::Write;
@@ -200,20 +740,146 @@ fn select_editor() -> String {
     }
 }
 
-/// Checks if the remote branch even exists
-/// and if the local one is up to date with it.
-fn should_delete_remote_branch(remote: &str, branch: &str) -> Result<bool> {
-    let remote_rev = Command::new("git")
-        .args(["rev-parse", &format!("{remote}/{branch}")])
+/// Deletes the branches listed in the staging file from the remote in
+/// --remote-only mode.
+fn prune_remote_branches(
+    backend: &dyn Backend,
+    remote: &str,
+    entries: &[Entry],
+    cli_args: &Cli,
+) -> Result<()> {
+    let mut branches = Vec::new();
+    for Entry { branch, .. } in entries {
+        if let Some(commit) = backend.resolve(&format!("refs/remotes/{remote}/{branch}"))? {
+            branches.push((*branch, commit));
+        }
+    }
+
+    if cli_args.dry_run {
+        for (branch, _) in branches {
+            println!("Would delete remote branch '{remote}/{branch}'");
+        }
+        std::process::exit(DRY_RUN_EXIT_CODE);
+    }
+
+    let deleted = delete_remote_branches(remote, &branches)?;
+    // the commits are still around locally, even without the remote ref
+    let journal = Journal::open(cli_args.backup_refs)?;
+    for (branch, commit) in &branches {
+        if !deleted.contains(branch) {
+            continue;
+        }
+        if let Err(e) = journal.backup(branch, commit) {
+            println!("Failed to back up remote branch '{remote}/{branch}':\n{e}");
+        }
+        if let Err(e) = journal.record_remote_only(branch, commit, remote) {
+            println!("Failed to log deleted branch '{remote}/{branch}', undo won't find it:\n{e}");
+        }
+    }
+    Ok(())
+}
+
+/// Tags the tip of a branch as `archive/<branch>`, so its commits stay
+/// reachable after the branch is deleted.
+fn archive_branch(branch: &str, commit: &str) -> Result<()> {
+    let output = Command::new("git")
+        .args(["tag", &format!("archive/{branch}"), commit])
+        .output()?;
+    if !output.status.success() {
+        return Err(anyhow!("{}", String::from_utf8(output.stderr)?.trim_end()));
+    }
+    Ok(())
+}
+
+fn rename_branch(branch: &str, new_name: &str) -> Result<()> {
+    let output = Command::new("git")
+        .args(["branch", "--move", branch, new_name])
+        .output()?;
+    if !output.status.success() {
+        return Err(anyhow!("{}", String::from_utf8(output.stderr)?.trim_end()));
+    }
+    Ok(())
+}
+
+/// Deletes all given branches from the remote with a single push.
+/// Each branch is only deleted if the remote one still points to the
+/// expected commit, i.e. nobody pushed to it since we last fetched.
+/// Returns the branches that were deleted.
+fn delete_remote_branches<'a>(
+    remote: &str,
+    branches: &[(&'a str, String)],
+) -> Result<Vec<&'a str>> {
+    let mut deleted = Vec::new();
+    if branches.is_empty() {
+        return Ok(deleted);
+    }
+    let leases = branches
+        .iter()
+        .map(|(branch, expected_rev)| format!("--force-with-lease={branch}:{expected_rev}"));
+    let output = Command::new("git")
+        .args(["push", "--porcelain"])
+        .args(leases)
+        .args(["--delete", remote])
+        .args(branches.iter().map(|(branch, _)| branch))
         .output()?;
-    if !remote_rev.status.success() {
-        return Ok(false);
+
+    // lines look like "<flag>\t<from>:<to>\t<summary>", e.g.
+    // "!\t(delete):refs/heads/feature\t[rejected] (stale info)"
+    let stdout = String::from_utf8(output.stdout)?;
+    let mut results = HashMap::new();
+    for line in stdout.lines() {
+        let mut fields = line.split('\t');
+        let (Some(flag), Some(refs), Some(summary)) = (fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+        if let Some(branch) = refs
+            .rsplit(':')
+            .next()
+            .and_then(|to| to.strip_prefix("refs/heads/"))
+        {
+            results.insert(branch, (flag != "!", summary));
+        }
+    }
+
+    for (branch, _) in branches {
+        match results.get(branch) {
+            Some((true, _)) => {
+                println!("Deleted remote branch '{remote}/{branch}'");
+                deleted.push(*branch);
+            }
+            Some((false, summary)) => {
+                println!("Failed to delete remote branch '{remote}/{branch}': {summary}")
+            }
+            // git bailed out before talking to the remote
+            None => print!(
+                "Failed to delete remote branch '{remote}/{branch}':\n{}",
+                String::from_utf8_lossy(&output.stderr)
+            ),
+        }
     }
-    let remote_rev = remote_rev.stdout;
-    let local_rev = Command::new("git")
-        .args(["rev-parse", &format!("{remote}/{branch}")])
-        .output()?
-        .stdout;
+    Ok(deleted)
+}
+
+enum RemoteBranch {
+    Missing,
+    /// The local branch points to a different commit than the remote one.
+    Diverged,
+    /// Both point to the contained commit.
+    UpToDate(String),
+}
 
-    Ok(remote_rev == local_rev)
+/// Checks if the remote branch even exists
+/// and if the local one is up to date with it.
+fn check_remote_branch(backend: &dyn Backend, remote: &str, branch: &str) -> Result<RemoteBranch> {
+    let Some(remote_rev) = backend.resolve(&format!("refs/remotes/{remote}/{branch}"))? else {
+        return Ok(RemoteBranch::Missing);
+    };
+    let local_rev = backend.resolve(&format!("refs/heads/{branch}"))?;
+
+    if local_rev.as_ref() == Some(&remote_rev) {
+        Ok(RemoteBranch::UpToDate(remote_rev))
+    } else {
+        Ok(RemoteBranch::Diverged)
+    }
 }
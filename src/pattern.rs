@@ -0,0 +1,122 @@
+//! Matching branch names against user-supplied patterns.
+
+use std::str::FromStr;
+
+use regex::Regex;
+
+/// A glob, or a regular expression if prefixed with `re:`.
+/// Unlike globs, regular expressions match anywhere in the name
+/// unless anchored with `^` and `$`.
+#[derive(Clone, Debug)]
+pub enum Pattern {
+    Glob(String),
+    Regex(Regex),
+}
+
+impl Pattern {
+    pub fn matches(&self, name: &str) -> bool {
+        match self {
+            Pattern::Glob(glob) => glob_match(glob, name),
+            Pattern::Regex(regex) => regex.is_match(name),
+        }
+    }
+}
+
+impl FromStr for Pattern {
+    type Err = regex::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_prefix("re:") {
+            Some(regex) => Ok(Pattern::Regex(Regex::new(regex)?)),
+            None => Ok(Pattern::Glob(s.to_owned())),
+        }
+    }
+}
+
+/// Matches a branch name against a shell-style glob.
+/// `*` matches any sequence of characters, including `/`,
+/// so `release/*` also covers `release/1.x/hotfix`.
+/// `?` matches a single character and `[...]` a character class,
+/// negated with a leading `!` or `^`.
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+
+    let (mut p, mut n) = (0, 0);
+    // where to resume after the last `*` if the rest doesn't match
+    let mut backtrack = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+                continue;
+            }
+            Some('?') => {
+                p += 1;
+                n += 1;
+                continue;
+            }
+            Some('[') => {
+                if let Some((matches, len)) = match_class(&pattern[p..], name[n]) {
+                    if matches {
+                        p += len;
+                        n += 1;
+                        continue;
+                    }
+                } else if name[n] == '[' {
+                    // unterminated class, match literally
+                    p += 1;
+                    n += 1;
+                    continue;
+                }
+            }
+            Some(&c) if c == name[n] => {
+                p += 1;
+                n += 1;
+                continue;
+            }
+            _ => {}
+        }
+        match backtrack {
+            Some((star, star_n)) => {
+                // let the last `*` swallow one more character
+                backtrack = Some((star, star_n + 1));
+                p = star + 1;
+                n = star_n + 1;
+            }
+            None => return false,
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Matches a character against the class at the start of `pattern`.
+/// Returns whether it matched and the length of the class,
+/// or `None` if the class is not terminated.
+fn match_class(pattern: &[char], c: char) -> Option<(bool, usize)> {
+    let mut i = 1;
+    let negated = matches!(pattern.get(i), Some('!' | '^'));
+    if negated {
+        i += 1;
+    }
+    let mut matches = false;
+    let mut first = true;
+    loop {
+        match *pattern.get(i)? {
+            // a `]` right after the opening bracket is literal
+            ']' if !first => break,
+            lo if pattern.get(i + 1) == Some(&'-') && pattern.get(i + 2) != Some(&']') => {
+                let hi = *pattern.get(i + 2)?;
+                matches |= lo <= c && c <= hi;
+                i += 3;
+            }
+            other => {
+                matches |= other == c;
+                i += 1;
+            }
+        }
+        first = false;
+    }
+    Some((matches != negated, i + 1))
+}
@@ -0,0 +1,217 @@
+//! The staging file, in which the user reviews what happens to each branch.
+//!
+//! Like the todo list of `git rebase -i`, every line holds an action and a
+//! branch name, e.g. `delete-remote feature` or `r feature`. A branch name
+//! without an action is deleted, along with the remote branch if `-r` is in
+//! effect. Commented lines are kept.
+
+use std::collections::{HashMap, HashSet};
+
+use anyhow::Result;
+
+use crate::worktree::Worktree;
+
+/// What to do with a branch listed in the staging file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action<'a> {
+    /// Force-delete the local branch, and the remote one if `remote` is set.
+    Delete {
+        remote: bool,
+    },
+    Keep,
+    /// Tag the tip as `archive/<branch>` before deleting the local branch.
+    Archive,
+    /// Rename the local branch instead of deleting it.
+    Rename(&'a str),
+    /// Remove the worktree the branch is checked out in, then delete it.
+    RemoveWorktree,
+}
+
+/// A line of the staging file that names a branch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry<'a> {
+    /// The line number, starting at 1.
+    pub line: usize,
+    pub branch: &'a str,
+    pub action: Action<'a>,
+}
+
+/// What the branches in the edited staging file are checked against.
+pub struct Rules<'a> {
+    /// The branches that were listed in the staging file.
+    pub candidates: HashSet<&'a str>,
+    /// All local branches, as they are after editing.
+    pub local_branches: HashSet<String>,
+    /// Empty in detached HEAD state.
+    pub current_branch: &'a str,
+    pub is_protected: &'a dyn Fn(&str) -> bool,
+    /// Only deleting and keeping is supported, e.g. for remote branches.
+    pub only_delete: bool,
+    /// The branches that qualify for deletion, usually because they were
+    /// detected as merged.
+    pub merged: HashSet<&'a str>,
+    /// Branches checked out in other worktrees.
+    pub worktrees: &'a HashMap<String, Worktree>,
+    /// Checks if a worktree has uncommitted changes. Only called for the
+    /// worktrees that are to be removed.
+    pub is_dirty: &'a dyn Fn(&str) -> Result<bool>,
+}
+
+pub static FOOTER: &str = "
+# ^^^^^^^^^^^^^^^^^^^^^^^
+# ! TO BE FORCE-DELETED !
+#
+# Review and edit the above list of branches to be force-deleted.
+# To preserve a branch, remove it from the list or comment it.
+# To delete a commented branch, uncomment it.
+#
+# A branch name can be preceded by an action:
+# d, delete <branch>          = delete the local branch only
+# r, delete-remote <branch>   = delete the local and the remote branch
+# k, keep <branch>            = keep the branch
+# a, archive <branch>         = tag the tip as archive/<branch>, then delete
+# m, rename <branch> <new>    = rename the branch instead of deleting it
+# w, remove-worktree <branch> = remove the clean worktree the merged branch
+#                               is checked out in, then delete the branch";
+
+/// Parses the edited staging file. Branch names without an action are
+/// deleted remotely as well if `delete_remote` is set. Lines that can't be
+/// parsed are returned as problems along with their line number.
+pub fn parse(content: &str, delete_remote: bool) -> (Vec<Entry<'_>>, Vec<(usize, String)>) {
+    let mut entries = Vec::new();
+    let mut problems = Vec::new();
+    for (i, line) in content.lines().enumerate() {
+        let words: Vec<_> = strip_comment(line).split_whitespace().collect();
+        let entry = match words[..] {
+            [] => continue,
+            [branch] => Ok((
+                branch,
+                Action::Delete {
+                    remote: delete_remote,
+                },
+            )),
+            [action, ref args @ ..] => parse_action(action, args),
+        };
+        match entry {
+            Ok((branch, action)) => entries.push(Entry {
+                line: i + 1,
+                branch,
+                action,
+            }),
+            Err(problem) => problems.push((i + 1, problem)),
+        }
+    }
+    (entries, problems)
+}
+
+/// Parses the edited staging file and checks every branch that is not
+/// kept against `rules`. Returns the problems along with their line number.
+pub fn validate(content: &str, delete_remote: bool, rules: &Rules) -> Vec<(usize, String)> {
+    let (entries, mut problems) = parse(content, delete_remote);
+    let mut seen = HashSet::new();
+    for Entry {
+        line,
+        branch,
+        action,
+    } in entries
+    {
+        if !seen.insert(branch) {
+            problems.push((line, format!("branch '{branch}' is listed more than once")));
+            continue;
+        }
+        if action == Action::Keep {
+            continue;
+        }
+        let verb = match action {
+            Action::Rename(_) => "rename",
+            _ => "delete",
+        };
+        let problem = if rules.only_delete && !matches!(action, Action::Delete { .. }) {
+            "only delete and keep are supported for remote branches".to_owned()
+        } else if (rules.is_protected)(branch) {
+            format!("refusing to {verb} protected branch '{branch}'")
+        } else if branch == rules.current_branch {
+            format!("cannot {verb} the current branch '{branch}'")
+        } else if !rules.local_branches.contains(branch) {
+            format!("no branch named '{branch}'")
+        } else if !rules.candidates.contains(branch) {
+            format!("branch '{branch}' was not in the list")
+        } else if let Action::Rename(new_name) = action {
+            if !rules.local_branches.contains(new_name) {
+                continue;
+            }
+            format!("branch '{new_name}' already exists")
+        } else if action == Action::RemoveWorktree {
+            match rules.worktrees.get(branch) {
+                None => format!("branch '{branch}' is not checked out in a worktree"),
+                Some(_) if !rules.merged.contains(branch) => {
+                    format!("branch '{branch}' is not merged, refusing to remove its worktree")
+                }
+                Some(Worktree {
+                    path,
+                    prunable: true,
+                    ..
+                }) => format!("worktree '{path}' no longer exists, run 'git worktree prune'"),
+                Some(Worktree { path, .. }) => match (rules.is_dirty)(path) {
+                    Ok(false) => continue,
+                    Ok(true) => format!("worktree '{path}' has uncommitted changes"),
+                    Err(e) => e.to_string(),
+                },
+            }
+        } else if let Some(Worktree { path, .. }) = rules.worktrees.get(branch) {
+            format!("branch '{branch}' is checked out in '{path}', use remove-worktree")
+        } else {
+            continue;
+        };
+        problems.push((line, problem));
+    }
+    problems.sort_by_key(|(line, _)| *line);
+    problems
+}
+
+/// Prepended to the staging file when it is opened again because of
+/// problems. Returns the annotation, so it can be stripped again.
+pub fn annotate(problems: &[(usize, String)]) -> String {
+    // the annotation shifts the lines of the staging file down
+    let offset = problems.len() + 2;
+    let mut annotation =
+        "# Please fix these problems, or quit without saving to abort:\n".to_owned();
+    for (line, problem) in problems {
+        annotation.push_str(&format!("#   line {}: {problem}\n", line + offset));
+    }
+    annotation.push_str("#\n");
+    annotation
+}
+
+fn parse_action<'a>(action: &str, args: &[&'a str]) -> Result<(&'a str, Action<'a>), String> {
+    let action = match action {
+        "d" | "delete" => Action::Delete { remote: false },
+        "r" | "delete-remote" => Action::Delete { remote: true },
+        "k" | "keep" => Action::Keep,
+        "a" | "archive" => Action::Archive,
+        "w" | "remove-worktree" => Action::RemoveWorktree,
+        "m" | "rename" => match args {
+            [branch, new_name] => return Ok((branch, Action::Rename(new_name))),
+            _ => return Err("rename takes a branch and its new name".into()),
+        },
+        _ => return Err(format!("unknown action '{action}'")),
+    };
+    match args {
+        [branch] => Ok((branch, action)),
+        _ => Err("expected a single branch name after the action".into()),
+    }
+}
+
+/// Removes a comment, whether it spans the whole line or trails
+/// a branch name, as well as surrounding whitespace.
+fn strip_comment(line: &str) -> &str {
+    // branch names may contain '#', but not whitespace
+    let comment_start = line
+        .match_indices('#')
+        .map(|(i, _)| i)
+        .find(|&i| i == 0 || line[..i].ends_with(char::is_whitespace));
+    match comment_start {
+        Some(i) => line[..i].trim(),
+        None => line.trim(),
+    }
+}
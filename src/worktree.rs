@@ -0,0 +1,72 @@
+//! Worktrees from `git worktree list`, since a branch that is checked out
+//! in one of them can't be deleted.
+
+use std::process::Command;
+
+use anyhow::{anyhow, Result};
+
+pub struct Worktree {
+    pub path: String,
+    /// The short name of the checked out branch, `None` if detached.
+    pub branch: Option<String>,
+    /// The directory of the worktree is gone, `git worktree prune`
+    /// would remove it.
+    pub prunable: bool,
+}
+
+/// All worktrees of the repository, including the main one.
+pub fn list() -> Result<Vec<Worktree>> {
+    let output = Command::new("git")
+        .args(["worktree", "list", "--porcelain"])
+        .output()?;
+    if !output.status.success() {
+        return Err(anyhow!("fatal: failed to list worktrees"));
+    }
+
+    // one block per worktree, separated by empty lines:
+    // worktree /path/to/worktree
+    // HEAD <commit>
+    // branch refs/heads/feature
+    // prunable gitdir file points to non-existent location
+    let mut worktrees = Vec::new();
+    for line in String::from_utf8(output.stdout)?.lines() {
+        if let Some(path) = line.strip_prefix("worktree ") {
+            worktrees.push(Worktree {
+                path: path.to_owned(),
+                branch: None,
+                prunable: false,
+            });
+        } else if let Some(branch) = line.strip_prefix("branch refs/heads/") {
+            if let Some(worktree) = worktrees.last_mut() {
+                worktree.branch = Some(branch.to_owned());
+            }
+        } else if line == "prunable" || line.starts_with("prunable ") {
+            if let Some(worktree) = worktrees.last_mut() {
+                worktree.prunable = true;
+            }
+        }
+    }
+    Ok(worktrees)
+}
+
+/// Checks if a worktree has uncommitted changes or untracked files.
+pub fn is_dirty(path: &str) -> Result<bool> {
+    let output = Command::new("git")
+        .args(["-C", path, "status", "--porcelain"])
+        .output()?;
+    if !output.status.success() {
+        return Err(anyhow!("failed to get the status of worktree '{path}'"));
+    }
+    Ok(!output.stdout.is_empty())
+}
+
+/// Removes a worktree. Git refuses if it is dirty.
+pub fn remove(path: &str) -> Result<()> {
+    let output = Command::new("git")
+        .args(["worktree", "remove", path])
+        .output()?;
+    if !output.status.success() {
+        return Err(anyhow!("{}", String::from_utf8(output.stderr)?.trim_end()));
+    }
+    Ok(())
+}
@@ -0,0 +1,79 @@
+mod common;
+
+use common::Fixture;
+
+#[test]
+fn delete_remote_applies_per_branch() {
+    let fixture = Fixture::with_pushed_squashed(&["a", "b"]);
+    let run = fixture.quickprune_with_editor(&[], "printf 'delete-remote a\\nd b\\n' > \"$1\"");
+    assert!(run.stdout().contains("Deleted remote branch 'origin/a'"));
+    assert_eq!(fixture.branches(), ["main"]);
+    assert_eq!(fixture.remote_branches(), ["b", "main"]);
+}
+
+#[test]
+fn delete_keeps_remote_branch_despite_flag() {
+    let fixture = Fixture::with_pushed_squashed(&["a", "b"]);
+    fixture.quickprune_with_editor(&["-r"], "printf 'delete a\\nb\\n' > \"$1\"");
+    assert_eq!(fixture.branches(), ["main"]);
+    assert_eq!(fixture.remote_branches(), ["a", "main"]);
+}
+
+#[test]
+fn keep_preserves_branch() {
+    let fixture = Fixture::with_pushed_squashed(&["a", "b"]);
+    fixture.quickprune_with_editor(&[], "sed -i 's/^a /keep a /' \"$1\"");
+    assert_eq!(fixture.branches(), ["a", "main"]);
+}
+
+#[test]
+fn archive_tags_tip_before_deleting() {
+    let fixture = Fixture::with_pushed_squashed(&["a", "b"]);
+    let tip = fixture.git(&["rev-parse", "a"]);
+    let run = fixture.quickprune_with_editor(&[], "printf 'a a\\n' > \"$1\"");
+    assert!(run
+        .stdout()
+        .contains("Archived branch 'a' as tag 'archive/a'"));
+    assert_eq!(fixture.branches(), ["b", "main"]);
+    assert_eq!(fixture.git(&["rev-parse", "archive/a"]), tip);
+}
+
+#[test]
+fn archive_keeps_branch_if_tag_exists() {
+    let fixture = Fixture::with_pushed_squashed(&["a", "b"]);
+    fixture.git(&["tag", "archive/a", "main"]);
+    let run = fixture.quickprune_with_editor(&[], "printf 'archive a\\n' > \"$1\"");
+    assert!(run.stdout().contains("Failed to archive branch 'a'"));
+    assert_eq!(fixture.branches(), ["a", "b", "main"]);
+}
+
+#[test]
+fn rename_moves_branch() {
+    let fixture = Fixture::with_pushed_squashed(&["a", "b"]);
+    let run =
+        fixture.quickprune_with_editor(&[], "printf 'rename a old/a\\nm b old/b\\n' > \"$1\"");
+    assert!(run.stdout().contains("Renamed branch 'a' to 'old/a'"));
+    assert_eq!(fixture.branches(), ["main", "old/a", "old/b"]);
+}
+
+#[test]
+fn abbreviations_can_be_mixed() {
+    let fixture = Fixture::with_pushed_squashed(&["a", "b"]);
+    fixture.quickprune_with_editor(&[], "printf 'r a\\nm b c\\n' > \"$1\"");
+    assert_eq!(fixture.branches(), ["c", "main"]);
+    assert_eq!(fixture.remote_branches(), ["b", "main"]);
+}
+
+#[test]
+fn invalid_lines_are_problems() {
+    let fixture = Fixture::with_pushed_squashed(&["a", "b"]);
+    let run = fixture.quickprune_with_editor(
+        &[],
+        "printf 'frobnicate a\\nrename b\\nrename a b\\n' > \"$1\"",
+    );
+    let staging_file = run.staging_file.unwrap();
+    assert!(staging_file.contains("line 6: unknown action 'frobnicate'"));
+    assert!(staging_file.contains("line 7: rename takes"));
+    assert!(staging_file.contains("line 8: branch 'b' already exists"));
+    assert_eq!(fixture.branches(), ["a", "b", "main"]);
+}
@@ -0,0 +1,107 @@
+mod common;
+
+use common::Fixture;
+
+/// Builds a repo with squash-merged branches:
+/// `old` by Alice <alice@corp.com>, committed 100 days ago,
+/// and `new` by Bob <bob@example.com>, committed just now.
+fn fixture() -> Fixture {
+    let fixture = Fixture::new();
+    let hundred_days_ago = format!(
+        "@{}",
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            - 100 * 24 * 60 * 60
+    );
+
+    fixture.git(&["checkout", "-b", "old"]);
+    fixture.commit_file_with_env(
+        "old.txt",
+        "old\n",
+        "add old",
+        &[
+            ("GIT_COMMITTER_DATE", &hundred_days_ago),
+            ("GIT_AUTHOR_DATE", &hundred_days_ago),
+            ("GIT_AUTHOR_NAME", "Alice"),
+            ("GIT_AUTHOR_EMAIL", "alice@corp.com"),
+        ],
+    );
+    fixture.squash_merge("old");
+
+    fixture.git(&["checkout", "-b", "new"]);
+    fixture.commit_file_with_env(
+        "new.txt",
+        "new\n",
+        "add new",
+        &[
+            ("GIT_AUTHOR_NAME", "Bob"),
+            ("GIT_AUTHOR_EMAIL", "bob@example.com"),
+        ],
+    );
+    fixture.squash_merge("new");
+    fixture
+}
+
+#[test]
+fn older_than_skips_recent_branches() {
+    let fixture = fixture();
+    let run = fixture.quickprune(&["--older-than", "30d"]);
+    assert_eq!(run.listed(), ["old"]);
+    assert!(run
+        .stderr()
+        .contains("Skipped 1 branches newer than --older-than."));
+    assert_eq!(fixture.branches(), ["main", "new"]);
+}
+
+#[test]
+fn newer_than_skips_old_branches() {
+    let fixture = fixture();
+    let run = fixture.quickprune(&["--newer-than", "2w"]);
+    assert_eq!(run.listed(), ["new"]);
+    assert!(run
+        .stderr()
+        .contains("Skipped 1 branches older than --newer-than."));
+}
+
+#[test]
+fn age_filters_combine() {
+    let fixture = fixture();
+    let run = fixture.quickprune(&["--older-than", "1w", "--newer-than", "1y"]);
+    assert_eq!(run.listed(), ["old"]);
+
+    let run = fixture.quickprune(&["--older-than", "1y"]);
+    assert!(run.staging_file.is_none());
+}
+
+#[test]
+fn author_filters_by_email() {
+    let fixture = fixture();
+    let run = fixture.quickprune(&["--author", "*@example.com"]);
+    assert_eq!(run.listed(), ["new"]);
+    assert!(run
+        .stderr()
+        .contains("Skipped 1 branches not matching --author."));
+
+    let run = fixture.quickprune(&["--author", "re:^alice@"]);
+    assert_eq!(run.listed(), ["old"]);
+}
+
+#[test]
+fn author_filters_by_name() {
+    let fixture = fixture();
+    let run = fixture.quickprune(&["--author", "Alice"]);
+    assert_eq!(run.listed(), ["old"]);
+}
+
+#[test]
+fn rejects_invalid_durations() {
+    let fixture = fixture();
+    for duration in ["30", "d", "30x", "-1d", "999999999999999y"] {
+        let run = fixture.quickprune(&["--older-than", duration]);
+        assert!(!run.output.status.success(), "accepted {duration}");
+        assert!(!run.stderr().contains("panicked"), "panicked on {duration}");
+    }
+    assert_eq!(fixture.branches(), ["main", "new", "old"]);
+}
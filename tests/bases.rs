@@ -0,0 +1,76 @@
+mod common;
+
+use common::Fixture;
+
+/// Builds a repo with the maintenance branches `release/1.x` and `maint`,
+/// a branch `fix` squash-merged into `release/1.x` and an unmerged `wip`.
+fn fixture() -> Fixture {
+    let fixture = Fixture::new();
+    for branch in ["release/1.x", "maint"] {
+        fixture.git(&["checkout", "-b", branch, "main"]);
+        fixture.git(&["push", "origin", branch]);
+    }
+    fixture.git(&["checkout", "main"]);
+    fixture.git(&["branch", "-D", "maint"]);
+    fixture.git(&["checkout", "-b", "fix", "release/1.x"]);
+    fixture.commit_file("fix.txt", "fix\n", "fix a bug");
+    fixture.git(&["checkout", "release/1.x"]);
+    fixture.git(&["merge", "--squash", "fix"]);
+    fixture.git(&["commit", "-m", "squashed fix"]);
+    fixture.git(&["push", "origin", "release/1.x"]);
+    fixture.git(&["checkout", "-b", "wip", "main"]);
+    fixture.commit_file("wip.txt", "wip\n", "work in progress");
+    fixture.git(&["checkout", "main"]);
+    fixture
+}
+
+#[test]
+fn only_main_is_a_base_by_default() {
+    let fixture = fixture();
+    let run = fixture.quickprune(&[]);
+    assert!(run.listed().is_empty());
+}
+
+#[test]
+fn branch_merged_into_glob_base_is_listed() {
+    let fixture = fixture();
+    let run = fixture.quickprune(&["--base", "release/*"]);
+    assert_eq!(
+        run.listed_with_comments()
+            .into_iter()
+            .map(|(branch, comment)| (branch, comment.split(',').next().unwrap().to_owned()))
+            .collect::<Vec<_>>(),
+        [(
+            "fix".to_owned(),
+            "rebased into origin/release/1.x".to_owned()
+        )]
+    );
+}
+
+#[test]
+fn local_base_branches_are_not_offered() {
+    let fixture = fixture();
+    fixture.git(&["checkout", "-b", "maint", "origin/maint"]);
+    fixture.commit_file("maint.txt", "maint\n", "maintenance");
+    fixture.git(&["push", "origin", "maint"]);
+    fixture.git(&["checkout", "main"]);
+    let run = fixture.quickprune(&["--base", "maint", "--dry-run"]);
+    assert!(!run.stdout().contains("'maint'"));
+}
+
+#[test]
+fn reads_bases_from_config() {
+    let fixture = fixture();
+    fixture.git(&["config", "--add", "quickprune.base", "release/*"]);
+    let run = fixture.quickprune(&["--dry-run"]);
+    assert_eq!(run.stdout(), "Would delete branch 'fix'\n");
+}
+
+#[test]
+fn warns_about_base_matching_nothing() {
+    let fixture = fixture();
+    let run = fixture.quickprune(&["--base", "stable/*", "--dry-run"]);
+    assert!(run
+        .stderr()
+        .contains("No branch on 'origin' matches --base 'stable/*'."));
+}
@@ -0,0 +1,101 @@
+mod common;
+
+use common::Fixture;
+
+/// The listed branches with the detection reason from their comment.
+fn reasons(fixture: &Fixture, args: &[&str]) -> Vec<(String, String)> {
+    fixture
+        .quickprune(args)
+        .listed_with_comments()
+        .into_iter()
+        .map(|(branch, comment)| (branch, comment.split(',').next().unwrap().to_owned()))
+        .collect()
+}
+
+#[test]
+fn classifies_fast_forward_as_ancestor() {
+    let fixture = Fixture::new();
+    fixture.git(&["checkout", "-b", "feature"]);
+    fixture.commit_file("a", "a\n", "add a");
+    fixture.git(&["checkout", "main"]);
+    fixture.git(&["merge", "--ff-only", "feature"]);
+    fixture.git(&["push", "origin", "main"]);
+
+    assert_eq!(
+        reasons(&fixture, &[]),
+        [("feature".into(), "ancestor".into())]
+    );
+}
+
+#[test]
+fn classifies_merge_commit_as_ancestor() {
+    let fixture = Fixture::new();
+    fixture.git(&["checkout", "-b", "feature"]);
+    fixture.commit_file("a", "a\n", "add a");
+    fixture.git(&["checkout", "main"]);
+    fixture.commit_file("b", "b\n", "add b");
+    fixture.git(&["merge", "--no-ff", "-m", "merge feature", "feature"]);
+    fixture.git(&["push", "origin", "main"]);
+
+    assert_eq!(
+        reasons(&fixture, &[]),
+        [("feature".into(), "ancestor".into())]
+    );
+}
+
+#[test]
+fn classifies_rebase_merge_as_rebased() {
+    let fixture = Fixture::new();
+    fixture.git(&["checkout", "-b", "feature"]);
+    fixture.commit_file("a", "a\n", "add a");
+    fixture.commit_file("b", "b\n", "add b");
+    fixture.git(&["checkout", "main"]);
+    fixture.commit_file("c", "c\n", "add c");
+    fixture.git(&["cherry-pick", "main..feature"]);
+    fixture.git(&["push", "origin", "main"]);
+
+    assert_eq!(
+        reasons(&fixture, &[]),
+        [("feature".into(), "rebased".into())]
+    );
+}
+
+#[test]
+fn classifies_squash_merge_as_squashed() {
+    for strategy in ["tree", "patch-id", "all"] {
+        let fixture = Fixture::new();
+        fixture.git(&["checkout", "-b", "feature"]);
+        fixture.commit_file("a", "a\n", "add a");
+        fixture.commit_file("b", "b\n", "add b");
+        fixture.squash_merge("feature");
+
+        assert_eq!(
+            reasons(&fixture, &["--strategy", strategy]),
+            [("feature".into(), "squashed".into())]
+        );
+    }
+}
+
+#[test]
+fn classification_comment_does_not_break_deletion() {
+    let fixture = Fixture::new();
+    fixture.git(&["checkout", "-b", "feature"]);
+    fixture.commit_file("a", "a\n", "add a");
+    fixture.squash_merge("feature");
+
+    fixture.quickprune(&[]);
+    assert_eq!(fixture.branches(), ["main"]);
+}
+
+#[test]
+fn reason_comment_after_branch_name_with_hash() {
+    let fixture = Fixture::new();
+    fixture.git(&["checkout", "-b", "fix#123"]);
+    fixture.commit_file("a", "a\n", "add a");
+    fixture.squash_merge("fix#123");
+
+    // the staging file line is "fix#123 # rebased, ..."
+    let run = fixture.quickprune(&[]);
+    assert_eq!(run.listed(), ["fix#123"]);
+    assert_eq!(fixture.branches(), ["main"]);
+}
@@ -0,0 +1,283 @@
+#![allow(dead_code)]
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+use tempfile::TempDir;
+
+/// A local clone with a bare "remote" next to it, both living in a
+/// temporary directory.
+pub struct Fixture {
+    dir: TempDir,
+    main: String,
+}
+
+impl Fixture {
+    pub fn new() -> Self {
+        Self::with_main("main")
+    }
+
+    /// Creates a fixture whose main branch has the given name.
+    pub fn with_main(main: &str) -> Self {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("gitconfig"), "").unwrap();
+        let fixture = Self {
+            dir,
+            main: main.to_owned(),
+        };
+        fixture.git_in(
+            fixture.dir.path(),
+            &["init", "--bare", "-b", main, "remote.git"],
+        );
+        fixture.git_in(fixture.dir.path(), &["clone", "remote.git", "local"]);
+        fixture.commit_file("README", "hello\n", "initial commit");
+        fixture.git(&["push", "origin", main]);
+        fixture
+    }
+
+    /// Creates a fixture where all given branches were squash-merged.
+    pub fn with_squashed(branches: &[&str]) -> Self {
+        let fixture = Self::new();
+        for (i, branch) in branches.iter().enumerate() {
+            fixture.git(&["checkout", "-b", branch, "main"]);
+            fixture.commit_file(&format!("file-{i}"), "content\n", "add file");
+            fixture.squash_merge(branch);
+        }
+        fixture
+    }
+
+    /// Creates a fixture where all given branches were pushed
+    /// and squash-merged.
+    pub fn with_pushed_squashed(branches: &[&str]) -> Self {
+        let fixture = Self::new();
+        for branch in branches {
+            fixture.git(&["checkout", "-b", branch, "main"]);
+            fixture.commit_file(&format!("{branch}.txt"), "content\n", "add file");
+            fixture.git(&["push", "origin", branch]);
+            fixture.squash_merge(branch);
+        }
+        fixture
+    }
+
+    pub fn path(&self) -> PathBuf {
+        self.dir.path().join("local")
+    }
+
+    fn command(&self, program: &str, dir: &Path) -> Command {
+        let mut cmd = Command::new(program);
+        cmd.current_dir(dir)
+            .env("GIT_CONFIG_GLOBAL", self.dir.path().join("gitconfig"))
+            .env("GIT_CONFIG_NOSYSTEM", "1")
+            .env("GIT_AUTHOR_NAME", "Test")
+            .env("GIT_AUTHOR_EMAIL", "test@example.com")
+            .env("GIT_COMMITTER_NAME", "Test")
+            .env("GIT_COMMITTER_EMAIL", "test@example.com");
+        cmd
+    }
+
+    pub fn git_in(&self, dir: &Path, args: &[&str]) -> String {
+        let output = self.command("git", dir).args(args).output().unwrap();
+        assert!(
+            output.status.success(),
+            "git {args:?} failed:\n{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8(output.stdout).unwrap()
+    }
+
+    /// Clones the remote a second time, e.g. to simulate a coworker.
+    pub fn second_clone(&self) -> PathBuf {
+        self.git_in(self.dir.path(), &["clone", "remote.git", "other"]);
+        self.dir.path().join("other")
+    }
+
+    pub fn remote_branches(&self) -> Vec<String> {
+        self.git_in(
+            &self.dir.path().join("remote.git"),
+            &["branch", "--format", "%(refname:short)"],
+        )
+        .lines()
+        .map(str::to_owned)
+        .collect()
+    }
+
+    pub fn git(&self, args: &[&str]) -> String {
+        self.git_in(&self.path(), args)
+    }
+
+    pub fn commit_file(&self, name: &str, content: &str, message: &str) {
+        self.commit_file_with_env(name, content, message, &[]);
+    }
+
+    /// Commits a file with extra environment variables for git,
+    /// e.g. to set the date or author of the commit.
+    pub fn commit_file_with_env(
+        &self,
+        name: &str,
+        content: &str,
+        message: &str,
+        env: &[(&str, &str)],
+    ) {
+        let path = self.path().join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+        self.git(&["add", name]);
+        let output = self
+            .command("git", &self.path())
+            .envs(env.iter().copied())
+            .args(["commit", "-m", message])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+    }
+
+    /// Adds `count` commits to the current branch, each changing the file
+    /// `name`. Much faster than committing them one by one.
+    pub fn commit_many(&self, name: &str, count: usize) {
+        use std::io::Write;
+
+        let branch = self.git(&["symbolic-ref", "HEAD"]);
+        let branch = branch.trim();
+        let mut stream = format!("reset {branch}\nfrom {branch}^0\n");
+        for i in 0..count {
+            let content = format!("{i}\n");
+            stream.push_str(&format!(
+                "commit {branch}\ncommitter Test <test@example.com> {i} +0000\ndata 6\nchange\n\
+                 M 644 inline {name}\ndata {}\n{content}\n",
+                content.len()
+            ));
+        }
+        let mut fast_import = self
+            .command("git", &self.path())
+            .args(["fast-import", "--quiet"])
+            .stdin(std::process::Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut stdin = fast_import.stdin.take().unwrap();
+        stdin.write_all(stream.as_bytes()).unwrap();
+        drop(stdin);
+        assert!(fast_import.wait().unwrap().success());
+        self.git(&["reset", "--hard"]);
+    }
+
+    /// Squash-merges `branch` into main and pushes main.
+    pub fn squash_merge(&self, branch: &str) {
+        self.git(&["checkout", &self.main]);
+        self.git(&["merge", "--squash", branch]);
+        self.git(&["commit", "-m", &format!("squashed {branch}")]);
+        self.git(&["push", "origin", &self.main]);
+    }
+
+    pub fn branches(&self) -> Vec<String> {
+        self.git(&["branch", "--format", "%(refname:short)"])
+            .lines()
+            .map(str::to_owned)
+            .collect()
+    }
+
+    /// Runs git-qp with an editor that records the staging file it was
+    /// given and leaves it unchanged.
+    pub fn quickprune(&self, args: &[&str]) -> Run {
+        self.quickprune_with_editor(args, "")
+    }
+
+    /// Runs git-qp with an editor that records the staging file it was
+    /// given and then runs `script`, which finds the file's path in `$1`.
+    pub fn quickprune_with_editor(&self, args: &[&str], script: &str) -> Run {
+        let editor = self.dir.path().join("editor.sh");
+        let capture = self.dir.path().join("staging-file");
+        let _ = fs::remove_file(&capture);
+        fs::write(
+            &editor,
+            format!("#!/bin/sh\ncp \"$1\" {capture:?}\n{script}\n"),
+        )
+        .unwrap();
+        make_executable(&editor);
+
+        let output = self
+            .command(env!("CARGO_BIN_EXE_git-qp"), &self.path())
+            .env("EDITOR", &editor)
+            .args(args)
+            .output()
+            .unwrap();
+        Run {
+            output,
+            staging_file: fs::read_to_string(capture).ok(),
+        }
+    }
+}
+
+/// Builds a repo with the given main branch
+/// and a pushed branch `feature` that was squash-merged into it.
+pub fn squashed_feature(main: &str) -> Fixture {
+    let fixture = Fixture::with_main(main);
+    fixture.git(&["checkout", "-b", "feature"]);
+    fixture.commit_file("a", "a\n", "add a");
+    fixture.git(&["push", "origin", "feature"]);
+    fixture.squash_merge("feature");
+    fixture
+}
+
+/// Builds a repo where `feature` was squash-merged and the same line was
+/// then changed again on main `follow_ups` times.
+pub fn conflict_after_squash(follow_ups: usize) -> Fixture {
+    let fixture = Fixture::new();
+    fixture.git(&["checkout", "-b", "feature"]);
+    fixture.commit_file("README", "hello world\n", "change greeting");
+    fixture.squash_merge("feature");
+    for i in 0..follow_ups {
+        fixture.commit_file(
+            "README",
+            &format!("hello world {i}\n"),
+            "change greeting again",
+        );
+    }
+    fixture.git(&["push", "origin", "main"]);
+    fixture
+}
+
+pub struct Run {
+    pub output: Output,
+    pub staging_file: Option<String>,
+}
+
+impl Run {
+    pub fn stdout(&self) -> String {
+        String::from_utf8_lossy(&self.output.stdout).into_owned()
+    }
+
+    pub fn stderr(&self) -> String {
+        String::from_utf8_lossy(&self.output.stderr).into_owned()
+    }
+
+    /// Branches listed for deletion in the staging file.
+    pub fn listed(&self) -> Vec<String> {
+        self.listed_with_comments()
+            .into_iter()
+            .map(|(branch, _)| branch)
+            .collect()
+    }
+
+    /// Branches listed for deletion in the staging file,
+    /// along with their trailing comment.
+    pub fn listed_with_comments(&self) -> Vec<(String, String)> {
+        self.staging_file
+            .as_deref()
+            .unwrap_or_default()
+            .lines()
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| match line.split_once(" # ") {
+                Some((branch, comment)) => (branch.trim().to_owned(), comment.trim().to_owned()),
+                None => (line.trim().to_owned(), String::new()),
+            })
+            .collect()
+    }
+}
+
+fn make_executable(path: &Path) {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o755)).unwrap();
+}
@@ -0,0 +1,70 @@
+mod common;
+
+use common::squashed_feature;
+
+#[test]
+fn reads_main_branch() {
+    let fixture = squashed_feature("stable");
+    fixture.git(&["config", "quickprune.mainBranch", "stable"]);
+
+    let run = fixture.quickprune(&["--dry-run"]);
+    assert_eq!(run.stdout(), "Would delete branch 'feature'\n");
+
+    let run = fixture.quickprune(&["--dry-run", "--main-branch", "main"]);
+    assert!(run.stderr().contains("main branch 'main' not found"));
+}
+
+#[test]
+fn reads_remote() {
+    let fixture = squashed_feature("main");
+    fixture.git(&["remote", "rename", "origin", "upstream"]);
+
+    let run = fixture.quickprune(&["--dry-run"]);
+    assert!(!run.output.status.success());
+
+    fixture.git(&["config", "quickprune.remote", "upstream"]);
+    let run = fixture.quickprune(&["--dry-run"]);
+    assert_eq!(run.stdout(), "Would delete branch 'feature'\n");
+
+    let run = fixture.quickprune(&["--dry-run", "--remote", "origin"]);
+    assert!(!run.output.status.success());
+}
+
+#[test]
+fn reads_delete_remote() {
+    let fixture = squashed_feature("main");
+    fixture.git(&["config", "quickprune.deleteRemote", "yes"]);
+
+    let run = fixture.quickprune(&["--dry-run"]);
+    assert!(run
+        .stdout()
+        .contains("Would delete remote branch 'origin/feature'"));
+
+    let run = fixture.quickprune(&["--dry-run", "--keep-remote-branches"]);
+    assert_eq!(run.stdout(), "Would delete branch 'feature'\n");
+
+    fixture.quickprune(&["--yes"]);
+    assert_eq!(fixture.remote_branches(), ["main"]);
+}
+
+#[test]
+fn repository_config_overrides_global_config() {
+    let fixture = squashed_feature("stable");
+    fixture.git(&["config", "--global", "quickprune.mainBranch", "stable"]);
+
+    let run = fixture.quickprune(&["--dry-run"]);
+    assert_eq!(run.stdout(), "Would delete branch 'feature'\n");
+
+    fixture.git(&["config", "quickprune.mainBranch", "main"]);
+    let run = fixture.quickprune(&["--dry-run"]);
+    assert!(run.stderr().contains("main branch 'main' not found"));
+}
+
+#[test]
+fn rejects_invalid_boolean() {
+    let fixture = squashed_feature("main");
+    fixture.git(&["config", "quickprune.deleteRemote", "perhaps"]);
+
+    let run = fixture.quickprune(&["--dry-run"]);
+    assert!(run.stderr().contains("failed to read git config"));
+}
@@ -0,0 +1,169 @@
+mod common;
+
+use common::Fixture;
+
+/// Builds a repo with the squash-merged branches `merged-1` and `merged-2`
+/// and an unmerged branch `unmerged`.
+fn fixture() -> Fixture {
+    let fixture = Fixture::new();
+    fixture.git(&["checkout", "-b", "unmerged"]);
+    fixture.commit_file("a", "a\n", "add a");
+    for branch in ["merged-1", "merged-2"] {
+        fixture.git(&["checkout", "-b", branch, "main"]);
+        fixture.commit_file(&format!("{branch}.txt"), "content\n", "add file");
+        fixture.squash_merge(branch);
+    }
+    fixture
+}
+
+#[test]
+fn staging_file_lists_merged_and_comments_unmerged() {
+    let fixture = fixture();
+    let run = fixture.quickprune_with_editor(&[], "printf '' > \"$1\"");
+    let staging_file = run.staging_file.unwrap();
+    let lines: Vec<_> = staging_file.lines().collect();
+    // single-commit squash merges are patch-equivalent to a rebase
+    let sha = fixture.git(&["rev-parse", "--short=7", "merged-1"]);
+    assert!(lines[0].starts_with(&format!("merged-1   # rebased, {}, ", sha.trim())));
+    assert!(lines[0].ends_with(" old: add file"));
+    assert!(lines[1].starts_with("merged-2   # rebased, "));
+    let sha = fixture.git(&["rev-parse", "--short=7", "unmerged"]);
+    assert!(lines[2].starts_with(&format!("# unmerged # {}, ", sha.trim())));
+    assert!(lines[2].ends_with(" old: add a"));
+}
+
+#[test]
+fn staging_file_shows_upstream() {
+    let fixture = fixture();
+    fixture.git(&["checkout", "unmerged"]);
+    fixture.git(&["push", "--set-upstream", "origin", "unmerged"]);
+    fixture.commit_file("b", "b\n", "add b");
+    fixture.git(&["checkout", "merged-1"]);
+    fixture.git(&["push", "--set-upstream", "origin", "merged-1"]);
+    fixture.git(&["checkout", "main"]);
+
+    let run = fixture.quickprune_with_editor(&[], "printf '' > \"$1\"");
+    let staging_file = run.staging_file.unwrap();
+    let lines: Vec<_> = staging_file.lines().collect();
+    assert!(lines[0].ends_with(" old, origin/merged-1: add file"));
+    assert!(lines[2].ends_with(" old, origin/unmerged [ahead 1]: add b"));
+}
+
+#[test]
+fn commented_branch_is_kept() {
+    let fixture = fixture();
+    fixture.quickprune_with_editor(&[], "sed -i 's/^merged-1/# merged-1/' \"$1\"");
+    assert_eq!(fixture.branches(), ["main", "merged-1", "unmerged"]);
+}
+
+#[test]
+fn removed_branch_is_kept() {
+    let fixture = fixture();
+    fixture.quickprune_with_editor(&[], "sed -i '/^merged-2/d' \"$1\"");
+    assert_eq!(fixture.branches(), ["main", "merged-2", "unmerged"]);
+}
+
+#[test]
+fn uncommented_branch_is_deleted() {
+    let fixture = fixture();
+    fixture.quickprune_with_editor(&[], "sed -i 's/^# unmerged/unmerged/' \"$1\"");
+    assert_eq!(fixture.branches(), ["main"]);
+}
+
+#[test]
+fn indented_branch_is_deleted() {
+    let fixture = fixture();
+    fixture.quickprune_with_editor(&[], "printf '  merged-1  \\n\\n' > \"$1\"");
+    assert_eq!(fixture.branches(), ["main", "merged-2", "unmerged"]);
+}
+
+#[test]
+fn unknown_branch_reopens_editor() {
+    let fixture = fixture();
+    let run = fixture.quickprune_with_editor(
+        &[],
+        "if grep -q '^typo' \"$1\"; then sed -i '/^typo/d' \"$1\"; else printf '\\ntypo\\n' >> \"$1\"; fi",
+    );
+    let staging_file = run.staging_file.unwrap();
+    assert!(staging_file.starts_with("# Please fix these problems"));
+    assert!(staging_file.contains("no branch named 'typo'"));
+    assert_eq!(fixture.branches(), ["main", "unmerged"]);
+}
+
+#[test]
+fn unchanged_file_with_problems_aborts() {
+    let fixture = fixture();
+    let run = fixture.quickprune_with_editor(
+        &[],
+        "grep -q '^typo' \"$1\" || printf '\\ntypo\\n' >> \"$1\"",
+    );
+    assert!(run.stdout().contains("Aborted"));
+    assert_eq!(
+        fixture.branches(),
+        ["main", "merged-1", "merged-2", "unmerged"]
+    );
+}
+
+#[test]
+fn branches_not_offered_are_problems() {
+    let fixture = fixture();
+    let run =
+        fixture.quickprune_with_editor(&["--only", "merged-*"], "printf 'unmerged\\n' > \"$1\"");
+    assert!(run
+        .staging_file
+        .unwrap()
+        .contains("branch 'unmerged' was not in the list"));
+
+    fixture.git(&["checkout", "unmerged"]);
+    let run = fixture.quickprune_with_editor(&[], "printf 'unmerged\\n' > \"$1\"");
+    assert!(run
+        .staging_file
+        .unwrap()
+        .contains("cannot delete the current branch 'unmerged'"));
+    assert_eq!(
+        fixture.branches(),
+        ["main", "merged-1", "merged-2", "unmerged"]
+    );
+}
+
+#[test]
+fn editor_opens_without_candidates_only_if_forced() {
+    let fixture = Fixture::new();
+    fixture.git(&["checkout", "-b", "unmerged"]);
+    fixture.commit_file("a", "a\n", "add a");
+    fixture.git(&["checkout", "main"]);
+
+    let run = fixture.quickprune(&[]);
+    assert!(run.staging_file.is_none());
+    assert!(run.stdout().contains("Use -e to force-open the editor."));
+
+    let run = fixture.quickprune(&["-e"]);
+    assert!(run.staging_file.unwrap().starts_with("# unmerged # "));
+    assert_eq!(fixture.branches(), ["main", "unmerged"]);
+}
+
+#[test]
+fn failing_editor_aborts() {
+    let fixture = fixture();
+    let run = fixture.quickprune_with_editor(&[], "exit 1");
+    assert!(!run.output.status.success());
+    assert!(run.stderr().contains("the editor exited with"));
+    assert_eq!(
+        fixture.branches(),
+        ["main", "merged-1", "merged-2", "unmerged"]
+    );
+}
+
+#[test]
+fn emptied_file_deletes_nothing() {
+    let fixture = fixture();
+    let run = fixture.quickprune_with_editor(&[], "printf '\\n' > \"$1\"");
+    assert!(run.output.status.success());
+    assert!(run
+        .stdout()
+        .contains("The staging file is empty, no branches were deleted."));
+    assert_eq!(
+        fixture.branches(),
+        ["main", "merged-1", "merged-2", "unmerged"]
+    );
+}
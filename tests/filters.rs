@@ -0,0 +1,61 @@
+mod common;
+
+use common::Fixture;
+
+static BRANCHES: &[&str] = &["feature/a", "feature/b", "wip/c", "bugfix-12", "bugfix-x"];
+
+#[test]
+fn only_keeps_matching_branches() {
+    let fixture = Fixture::with_squashed(BRANCHES);
+    let run = fixture.quickprune(&["--only", "feature/*"]);
+    assert_eq!(run.listed(), ["feature/a", "feature/b"]);
+    assert!(run
+        .stderr()
+        .contains("Skipped 3 branches not matching --only."));
+    assert_eq!(
+        fixture.branches(),
+        ["bugfix-12", "bugfix-x", "main", "wip/c"]
+    );
+}
+
+#[test]
+fn only_can_be_repeated() {
+    let fixture = Fixture::with_squashed(BRANCHES);
+    let run = fixture.quickprune(&["--only", "feature/a", "--only", "wip/*"]);
+    assert_eq!(run.listed(), ["feature/a", "wip/c"]);
+}
+
+#[test]
+fn exclude_skips_matching_branches() {
+    let fixture = Fixture::with_squashed(BRANCHES);
+    let run = fixture.quickprune(&["--exclude", "wip/*", "--exclude", "bugfix-?"]);
+    assert_eq!(run.listed(), ["bugfix-12", "feature/a", "feature/b"]);
+    assert!(run
+        .stderr()
+        .contains("Skipped 2 branches matching --exclude."));
+}
+
+#[test]
+fn filters_accept_regexes() {
+    let fixture = Fixture::with_squashed(BRANCHES);
+    let run = fixture.quickprune(&["--only", r"re:^bugfix-\d+$", "--only", "re:/a"]);
+    assert_eq!(run.listed(), ["bugfix-12", "feature/a"]);
+}
+
+#[test]
+fn only_and_exclude_combine() {
+    let fixture = Fixture::with_squashed(BRANCHES);
+    let run = fixture.quickprune(&["--only", "feature/*", "--exclude", "re:b$"]);
+    assert_eq!(run.listed(), ["feature/a"]);
+    let stderr = run.stderr();
+    assert!(stderr.contains("Skipped 3 branches not matching --only."));
+    assert!(stderr.contains("Skipped 1 branches matching --exclude."));
+}
+
+#[test]
+fn rejects_invalid_regex() {
+    let fixture = Fixture::with_squashed(BRANCHES);
+    let run = fixture.quickprune(&["--only", "re:feature/("]);
+    assert!(!run.output.status.success());
+    assert_eq!(fixture.branches().len(), BRANCHES.len() + 1);
+}
@@ -0,0 +1,95 @@
+mod common;
+
+use std::path::{Path, PathBuf};
+
+use common::Fixture;
+
+/// Builds a fork workflow: main lives on `origin`, while `feature` was
+/// pushed to both `origin` and the remote `fork` before it was
+/// squash-merged. Returns the path of the fork as well.
+fn fixture() -> (Fixture, PathBuf) {
+    let fixture = Fixture::new();
+    let fork = fixture.path().parent().unwrap().join("fork.git");
+    fixture.git(&["init", "--bare", fork.to_str().unwrap()]);
+    fixture.git(&["remote", "add", "fork", fork.to_str().unwrap()]);
+    fixture.git(&["checkout", "-b", "feature"]);
+    fixture.commit_file("a", "a\n", "add a");
+    fixture.git(&["push", "origin", "feature"]);
+    fixture.git(&["push", "fork", "feature"]);
+    fixture.squash_merge("feature");
+    (fixture, fork)
+}
+
+fn fork_branches(fixture: &Fixture, fork: &Path) -> Vec<String> {
+    fixture
+        .git_in(fork, &["branch", "--format", "%(refname:short)"])
+        .lines()
+        .map(str::to_owned)
+        .collect()
+}
+
+#[test]
+fn upstream_and_push_remote_can_differ() {
+    let (fixture, fork) = fixture();
+    let run = fixture.quickprune(&[
+        "--upstream",
+        "origin",
+        "--push-remote",
+        "fork",
+        "-r",
+        "--yes",
+    ]);
+    assert!(run.stderr().contains("Comparing against 'origin/main'."));
+    assert!(run
+        .stdout()
+        .contains("Deleted remote branch 'fork/feature'"));
+    assert!(fork_branches(&fixture, &fork).is_empty());
+    assert_eq!(fixture.remote_branches(), ["feature", "main"]);
+}
+
+#[test]
+fn upstream_defaults_to_remote() {
+    let (fixture, _) = fixture();
+    let run = fixture.quickprune(&["--remote", "fork", "--dry-run"]);
+    assert!(run.stderr().contains("could not detect main branch"));
+
+    let run = fixture.quickprune(&[
+        "--remote",
+        "fork",
+        "--upstream",
+        "origin",
+        "-r",
+        "--dry-run",
+    ]);
+    assert!(run
+        .stdout()
+        .contains("Would delete remote branch 'fork/feature'"));
+}
+
+#[test]
+fn honors_push_default() {
+    let (fixture, fork) = fixture();
+    fixture.git(&["config", "remote.pushDefault", "fork"]);
+    fixture.quickprune(&["-r", "--yes"]);
+    assert!(fork_branches(&fixture, &fork).is_empty());
+    assert_eq!(fixture.remote_branches(), ["feature", "main"]);
+}
+
+#[test]
+fn branch_push_remote_overrides_push_default() {
+    let (fixture, fork) = fixture();
+    fixture.git(&["config", "remote.pushDefault", "origin"]);
+    fixture.git(&["config", "branch.feature.pushRemote", "fork"]);
+    fixture.quickprune(&["-r", "--yes"]);
+    assert!(fork_branches(&fixture, &fork).is_empty());
+    assert_eq!(fixture.remote_branches(), ["feature", "main"]);
+}
+
+#[test]
+fn push_remote_flag_overrides_config() {
+    let (fixture, fork) = fixture();
+    fixture.git(&["config", "remote.pushDefault", "fork"]);
+    fixture.quickprune(&["--push-remote", "origin", "-r", "--yes"]);
+    assert_eq!(fork_branches(&fixture, &fork), ["feature"]);
+    assert_eq!(fixture.remote_branches(), ["main"]);
+}
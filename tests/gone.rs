@@ -0,0 +1,83 @@
+mod common;
+
+use common::Fixture;
+
+/// Builds a repo with tracking branches: `feature` was merged and its
+/// upstream deleted, `abandoned` wasn't merged but its upstream was
+/// deleted, and `merged` was merged but its upstream still exists.
+fn fixture() -> Fixture {
+    let fixture = Fixture::new();
+    for branch in ["abandoned", "feature", "merged"] {
+        fixture.git(&["checkout", "-b", branch, "main"]);
+        fixture.commit_file(&format!("{branch}.txt"), "x\n", &format!("add {branch}"));
+        fixture.git(&["push", "-u", "origin", branch]);
+    }
+    fixture.squash_merge("feature");
+    fixture.squash_merge("merged");
+    fixture.git(&["push", "origin", "--delete", "abandoned", "feature"]);
+    fixture
+}
+
+/// The listed branches with the reasons from their comment,
+/// i.e. everything before the short commit id.
+fn reasons(run: &common::Run) -> Vec<(String, String)> {
+    run.listed_with_comments()
+        .into_iter()
+        .map(|(branch, comment)| {
+            let reasons: Vec<_> = comment
+                .split(", ")
+                .take_while(|field| !field.chars().all(|c| c.is_ascii_hexdigit()))
+                .collect();
+            (branch, reasons.join(", "))
+        })
+        .collect()
+}
+
+fn pairs(expected: &[(&str, &str)]) -> Vec<(String, String)> {
+    expected
+        .iter()
+        .map(|(branch, reason)| (branch.to_string(), reason.to_string()))
+        .collect()
+}
+
+#[test]
+fn gone_is_shown_next_to_merge_reason() {
+    let fixture = fixture();
+    let run = fixture.quickprune(&["--dry-run"]);
+    assert_eq!(
+        run.stdout(),
+        "Would delete branch 'feature'\nWould delete branch 'merged'\n"
+    );
+    let run = fixture.quickprune_with_editor(&[], "printf '' > \"$1\"");
+    assert_eq!(
+        reasons(&run),
+        pairs(&[("feature", "rebased, upstream gone"), ("merged", "rebased")])
+    );
+}
+
+#[test]
+fn gone_can_be_sufficient() {
+    let fixture = fixture();
+    let run = fixture.quickprune_with_editor(&["--gone", "sufficient"], "printf '' > \"$1\"");
+    assert_eq!(
+        reasons(&run),
+        pairs(&[
+            ("abandoned", "upstream gone"),
+            ("feature", "rebased, upstream gone"),
+            ("merged", "rebased"),
+        ])
+    );
+}
+
+#[test]
+fn gone_can_be_required() {
+    let fixture = fixture();
+    let run = fixture.quickprune_with_editor(&["--gone", "required"], "printf '' > \"$1\"");
+    assert_eq!(
+        reasons(&run),
+        pairs(&[("feature", "rebased, upstream gone")])
+    );
+    let staging_file = run.staging_file.unwrap();
+    assert!(staging_file.contains("# merged "));
+    assert!(staging_file.contains("# abandoned "));
+}
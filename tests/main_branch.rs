@@ -0,0 +1,53 @@
+mod common;
+
+use common::squashed_feature;
+
+#[test]
+fn uses_remote_head() {
+    let fixture = squashed_feature("stable");
+    fixture.git(&["remote", "set-head", "origin", "stable"]);
+
+    let run = fixture.quickprune(&["--dry-run"]);
+    assert!(run.stderr().contains("'origin/stable'"));
+    assert_eq!(run.stdout(), "Would delete branch 'feature'\n");
+}
+
+#[test]
+fn falls_back_to_init_default_branch() {
+    let fixture = squashed_feature("stable");
+    fixture.git(&["config", "init.defaultBranch", "stable"]);
+
+    let run = fixture.quickprune(&["--dry-run"]);
+    assert!(run.stderr().contains("'origin/stable'"));
+    assert_eq!(run.stdout(), "Would delete branch 'feature'\n");
+}
+
+#[test]
+fn falls_back_to_common_names() {
+    let fixture = squashed_feature("master");
+
+    let run = fixture.quickprune(&["--dry-run"]);
+    assert!(run.stderr().contains("'origin/master'"));
+    assert_eq!(run.stdout(), "Would delete branch 'feature'\n");
+}
+
+#[test]
+fn fails_if_main_branch_cannot_be_detected() {
+    let fixture = squashed_feature("stable");
+
+    let run = fixture.quickprune(&["--dry-run"]);
+    assert!(!run.output.status.success());
+    assert!(run.stderr().contains("could not detect main branch"));
+}
+
+#[test]
+fn explicit_main_branch_is_not_announced() {
+    let fixture = squashed_feature("stable");
+
+    let run = fixture.quickprune(&["--dry-run", "--main-branch", "stable"]);
+    assert_eq!(run.stderr(), "");
+    assert_eq!(run.stdout(), "Would delete branch 'feature'\n");
+
+    let run = fixture.quickprune(&["--dry-run", "--main-branch", "main"]);
+    assert!(run.stderr().contains("main branch 'main' not found"));
+}
@@ -0,0 +1,68 @@
+mod common;
+
+use common::Fixture;
+
+/// Builds a repo with a squash-merged branch `merged`
+/// and an unmerged branch `unmerged`.
+fn fixture() -> Fixture {
+    let fixture = Fixture::new();
+    fixture.git(&["checkout", "-b", "unmerged"]);
+    fixture.commit_file("a", "a\n", "add a");
+    fixture.git(&["checkout", "main"]);
+    fixture.git(&["checkout", "-b", "merged"]);
+    fixture.commit_file("b", "b\n", "add b");
+    fixture.squash_merge("merged");
+    fixture
+}
+
+#[test]
+fn yes_deletes_without_editor() {
+    let fixture = fixture();
+    let run = fixture.quickprune(&["--yes"]);
+    assert!(run.output.status.success());
+    assert!(run.staging_file.is_none());
+    assert!(run.stdout().contains("Deleted branch 'merged'"));
+    assert_eq!(fixture.branches(), ["main", "unmerged"]);
+}
+
+#[test]
+fn dry_run_deletes_nothing() {
+    let fixture = fixture();
+    let run = fixture.quickprune(&["--dry-run"]);
+    assert_eq!(run.output.status.code(), Some(3));
+    assert!(run.staging_file.is_none());
+    assert_eq!(run.stdout(), "Would delete branch 'merged'\n");
+    assert_eq!(fixture.branches(), ["main", "merged", "unmerged"]);
+}
+
+#[test]
+fn dry_run_succeeds_if_nothing_to_delete() {
+    let fixture = Fixture::new();
+    fixture.git(&["checkout", "-b", "unmerged"]);
+    fixture.commit_file("a", "a\n", "add a");
+    fixture.git(&["checkout", "main"]);
+
+    let run = fixture.quickprune(&["--dry-run"]);
+    assert_eq!(run.output.status.code(), Some(0));
+    assert_eq!(run.stdout(), "Nothing to do.\n");
+}
+
+#[test]
+fn yes_and_dry_run_conflict() {
+    let fixture = fixture();
+    let run = fixture.quickprune(&["--yes", "--dry-run"]);
+    assert!(!run.output.status.success());
+    assert_eq!(fixture.branches(), ["main", "merged", "unmerged"]);
+
+    // distinguishable from a dry run that found branches
+    let dry_run = fixture.quickprune(&["--dry-run"]);
+    assert_ne!(run.output.status.code(), dry_run.output.status.code());
+}
+
+#[test]
+fn dry_run_and_always_open_editor_conflict() {
+    let fixture = fixture();
+    let run = fixture.quickprune(&["--dry-run", "-e"]);
+    assert!(!run.output.status.success());
+    assert!(run.staging_file.is_none());
+}
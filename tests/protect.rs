@@ -0,0 +1,60 @@
+mod common;
+
+use common::Fixture;
+
+#[test]
+fn default_protected_branches_are_not_offered() {
+    let fixture =
+        Fixture::with_squashed(&["develop", "release/1.x", "release/2.x/hotfix", "feature"]);
+
+    let run = fixture.quickprune(&["-e"]);
+    assert_eq!(run.listed(), ["feature"]);
+    assert!(!run.staging_file.unwrap().contains("release"));
+    assert_eq!(
+        fixture.branches(),
+        ["develop", "main", "release/1.x", "release/2.x/hotfix"]
+    );
+}
+
+#[test]
+fn default_protection_can_be_disabled() {
+    let fixture = Fixture::with_squashed(&["develop", "feature"]);
+    let run = fixture.quickprune(&["--no-default-protect"]);
+    assert_eq!(run.listed(), ["develop", "feature"]);
+}
+
+#[test]
+fn protects_globs_from_command_line() {
+    let fixture = Fixture::with_squashed(&["fix-1", "fix-a", "keep/this", "feature", "feat"]);
+    let run = fixture.quickprune(&[
+        "--protect",
+        "fix-[0-9]",
+        "--protect",
+        "keep/*",
+        "--protect",
+        "fea?",
+    ]);
+    assert_eq!(run.listed(), ["feature", "fix-a"]);
+}
+
+#[test]
+fn protects_globs_from_config() {
+    let fixture = Fixture::with_squashed(&["personal/notes", "scratch", "feature"]);
+    fixture.git(&["config", "--add", "quickprune.protect", "personal/*"]);
+    fixture.git(&["config", "--add", "quickprune.protect", "scratch"]);
+
+    let run = fixture.quickprune(&[]);
+    assert_eq!(run.listed(), ["feature"]);
+}
+
+#[test]
+fn refuses_protected_branch_added_in_editor() {
+    let fixture = Fixture::with_squashed(&["develop", "feature"]);
+    let run = fixture.quickprune_with_editor(&[], "printf 'develop\\nfeature\\n' > \"$1\"");
+    assert!(run.stdout().contains("Aborted"));
+    assert!(run
+        .staging_file
+        .unwrap()
+        .contains("line 4: refusing to delete protected branch 'develop'"));
+    assert_eq!(fixture.branches(), ["develop", "feature", "main"]);
+}
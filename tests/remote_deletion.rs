@@ -0,0 +1,94 @@
+mod common;
+
+use common::Fixture;
+
+/// Builds a repo with a branch `feature` that was pushed
+/// and then squash-merged.
+fn fixture() -> Fixture {
+    let fixture = Fixture::new();
+    fixture.git(&["checkout", "-b", "feature"]);
+    fixture.commit_file("a", "a\n", "add a");
+    fixture.git(&["push", "origin", "feature"]);
+    fixture.squash_merge("feature");
+    fixture
+}
+
+#[test]
+fn deletes_remote_branch() {
+    let fixture = fixture();
+    let run = fixture.quickprune(&["--yes", "-r"]);
+    assert!(run
+        .stdout()
+        .contains("Deleted remote branch 'origin/feature'"));
+    assert_eq!(fixture.branches(), ["main"]);
+    assert_eq!(fixture.remote_branches(), ["main"]);
+}
+
+#[test]
+fn keeps_remote_branch_with_commits_missing_locally() {
+    let fixture = fixture();
+    let other = fixture.second_clone();
+    fixture.git_in(&other, &["checkout", "feature"]);
+    std::fs::write(other.join("b"), "b\n").unwrap();
+    fixture.git_in(&other, &["add", "b"]);
+    fixture.git_in(&other, &["commit", "-m", "add b"]);
+    fixture.git_in(&other, &["push", "origin", "feature"]);
+    fixture.git(&["fetch"]);
+
+    let run = fixture.quickprune(&["--dry-run", "-r"]);
+    assert!(run
+        .stdout()
+        .contains("Would keep remote branch 'origin/feature'"));
+
+    let run = fixture.quickprune(&["--yes", "-r"]);
+    assert!(run
+        .stdout()
+        .contains("Not deleting remote branch 'origin/feature'"));
+    assert_eq!(fixture.branches(), ["main"]);
+    assert_eq!(fixture.remote_branches(), ["feature", "main"]);
+}
+
+#[test]
+fn refuses_to_delete_remote_branch_pushed_to_since_fetch() {
+    let fixture = fixture();
+    let other = fixture.second_clone();
+    fixture.git_in(&other, &["checkout", "feature"]);
+    std::fs::write(other.join("b"), "b\n").unwrap();
+    fixture.git_in(&other, &["add", "b"]);
+    fixture.git_in(&other, &["commit", "-m", "add b"]);
+    fixture.git_in(&other, &["push", "origin", "feature"]);
+
+    let run = fixture.quickprune(&["--yes", "-r"]);
+    assert!(run
+        .stdout()
+        .contains("Failed to delete remote branch 'origin/feature'"));
+    assert_eq!(fixture.branches(), ["main"]);
+    assert_eq!(fixture.remote_branches(), ["feature", "main"]);
+}
+
+#[test]
+fn reports_remote_deletion_results_per_branch() {
+    let fixture = Fixture::new();
+    for branch in ["a", "b", "c"] {
+        fixture.git(&["checkout", "-b", branch, "main"]);
+        fixture.commit_file(
+            &format!("{branch}.txt"),
+            "content\n",
+            &format!("add {branch}"),
+        );
+        fixture.git(&["push", "origin", branch]);
+        fixture.squash_merge(branch);
+    }
+    let other = fixture.second_clone();
+    fixture.git_in(&other, &["checkout", "b"]);
+    std::fs::write(other.join("b.txt"), "changed\n").unwrap();
+    fixture.git_in(&other, &["commit", "-am", "change b"]);
+    fixture.git_in(&other, &["push", "origin", "b"]);
+
+    let run = fixture.quickprune(&["--yes", "-r"]);
+    let stdout = run.stdout();
+    assert!(stdout.contains("Deleted remote branch 'origin/a'"));
+    assert!(stdout.contains("Failed to delete remote branch 'origin/b': [rejected] (stale info)"));
+    assert!(stdout.contains("Deleted remote branch 'origin/c'"));
+    assert_eq!(fixture.remote_branches(), ["b", "main"]);
+}
@@ -0,0 +1,102 @@
+mod common;
+
+use common::Fixture;
+
+/// Builds a repo where the pushed branch `feature` was squash-merged and
+/// the pushed branch `wip` wasn't, and neither has a local copy anymore.
+fn fixture() -> Fixture {
+    let fixture = Fixture::new();
+    for branch in ["feature", "wip"] {
+        fixture.git(&["checkout", "-b", branch, "main"]);
+        fixture.commit_file(&format!("{branch}.txt"), "x\n", &format!("add {branch}"));
+        fixture.git(&["push", "origin", branch]);
+    }
+    fixture.squash_merge("feature");
+    fixture.git(&["branch", "-D", "feature", "wip"]);
+    fixture
+}
+
+#[test]
+fn prunes_merged_remote_branches_without_local_copy() {
+    let fixture = fixture();
+    let run = fixture.quickprune(&["--remote-only"]);
+    assert!(run.stderr().contains("Pruning branches on 'origin'."));
+    assert_eq!(run.listed(), ["feature"]);
+    assert!(run
+        .stdout()
+        .contains("Deleted remote branch 'origin/feature'"));
+    assert_eq!(fixture.remote_branches(), ["main", "wip"]);
+}
+
+#[test]
+fn dry_run_lists_remote_branches() {
+    let fixture = fixture();
+    let run = fixture.quickprune(&["--remote-only", "--dry-run"]);
+    assert_eq!(run.output.status.code(), Some(3));
+    assert_eq!(
+        run.stdout(),
+        "Would delete remote branch 'origin/feature'\n"
+    );
+    assert_eq!(fixture.remote_branches(), ["feature", "main", "wip"]);
+}
+
+#[test]
+fn main_is_not_offered() {
+    let fixture = fixture();
+    let run = fixture.quickprune(&["--remote-only", "--no-default-protect", "--dry-run"]);
+    assert!(!run.stdout().contains("main"));
+}
+
+#[test]
+fn prunes_push_remote() {
+    let fixture = fixture();
+    let fork = fixture.path().parent().unwrap().join("fork.git");
+    fixture.git(&["init", "--bare", fork.to_str().unwrap()]);
+    fixture.git(&["remote", "add", "fork", fork.to_str().unwrap()]);
+    fixture.git(&["fetch", "origin"]);
+    fixture.git(&["push", "fork", "origin/feature:refs/heads/feature"]);
+    fixture.git(&["fetch", "fork"]);
+    fixture.git(&["config", "remote.pushDefault", "fork"]);
+
+    let run = fixture.quickprune(&["--remote-only", "--upstream", "origin", "--yes"]);
+    assert!(run
+        .stdout()
+        .contains("Deleted remote branch 'fork/feature'"));
+    assert_eq!(fixture.remote_branches(), ["feature", "main", "wip"]);
+}
+
+#[test]
+fn only_delete_and_keep_are_supported() {
+    let fixture = fixture();
+    let run =
+        fixture.quickprune_with_editor(&["--remote-only"], "sed -i 's/^feature/a feature/' \"$1\"");
+    assert!(run
+        .staging_file
+        .unwrap()
+        .contains("only delete and keep are supported for remote branches"));
+    assert_eq!(fixture.remote_branches(), ["feature", "main", "wip"]);
+}
+
+#[test]
+fn undo_restores_remote_branch() {
+    let fixture = fixture();
+    fixture.quickprune(&["--remote-only", "--yes"]);
+    assert_eq!(fixture.remote_branches(), ["main", "wip"]);
+    fixture.quickprune(&["undo", "feature"]);
+    assert_eq!(fixture.remote_branches(), ["feature", "main", "wip"]);
+    assert_eq!(fixture.branches(), ["main"]);
+}
+
+#[test]
+fn undo_restores_remote_branch_with_local_copy() {
+    let fixture = fixture();
+    fixture.git(&["branch", "feature", "origin/feature"]);
+    fixture.quickprune(&["--remote-only", "--yes"]);
+    assert_eq!(fixture.remote_branches(), ["main", "wip"]);
+
+    let run = fixture.quickprune(&["undo", "--last-run"]);
+    assert!(run.output.status.success());
+    assert!(!run.stdout().contains("Failed"));
+    assert_eq!(fixture.remote_branches(), ["feature", "main", "wip"]);
+    assert_eq!(fixture.branches(), ["feature", "main"]);
+}
@@ -0,0 +1,72 @@
+mod common;
+
+use common::Fixture;
+
+#[test]
+fn works_in_detached_head_state() {
+    let fixture = Fixture::new();
+    fixture.git(&["checkout", "-b", "feature"]);
+    fixture.commit_file("a", "a\n", "add a");
+    fixture.squash_merge("feature");
+    fixture.git(&["checkout", "--detach", "main"]);
+
+    let run = fixture.quickprune(&[]);
+    assert_eq!(run.listed(), ["feature"]);
+    assert_eq!(
+        fixture.branches(),
+        ["(HEAD detached at refs/heads/main)", "main"]
+    );
+}
+
+#[test]
+fn skips_current_branch() {
+    let fixture = Fixture::new();
+    fixture.git(&["checkout", "-b", "feature"]);
+    fixture.commit_file("a", "a\n", "add a");
+    fixture.squash_merge("feature");
+    fixture.git(&["checkout", "feature"]);
+
+    let run = fixture.quickprune(&[]);
+    assert!(run.staging_file.is_none());
+    assert_eq!(fixture.branches(), ["feature", "main"]);
+}
+
+#[test]
+fn handles_branch_names_with_slashes() {
+    let fixture = Fixture::new();
+    fixture.git(&["checkout", "-b", "user/feature/thing"]);
+    fixture.commit_file("a", "a\n", "add a");
+    fixture.git(&["push", "origin", "user/feature/thing"]);
+    fixture.squash_merge("user/feature/thing");
+
+    let run = fixture.quickprune(&["-r"]);
+    assert_eq!(run.listed(), ["user/feature/thing"]);
+    assert!(run
+        .stdout()
+        .contains("Deleted remote branch 'origin/user/feature/thing'"));
+    assert_eq!(fixture.branches(), ["main"]);
+    assert_eq!(fixture.remote_branches(), ["main"]);
+}
+
+#[test]
+fn handles_branch_names_with_hashes() {
+    let fixture = Fixture::new();
+    fixture.git(&["checkout", "-b", "fix#123"]);
+    fixture.commit_file("a", "a\n", "add a");
+    fixture.squash_merge("fix#123");
+
+    let run = fixture.quickprune(&[]);
+    assert_eq!(run.listed(), ["fix#123"]);
+    assert_eq!(fixture.branches(), ["main"]);
+}
+
+#[test]
+fn fails_outside_of_repository() {
+    let dir = tempfile::tempdir().unwrap();
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_git-qp"))
+        .current_dir(dir.path())
+        .env("GIT_CEILING_DIRECTORIES", dir.path().parent().unwrap())
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+}
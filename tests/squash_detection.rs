@@ -0,0 +1,90 @@
+mod common;
+
+use common::{conflict_after_squash, Fixture};
+
+#[test]
+fn detects_squash_merge() {
+    let fixture = Fixture::new();
+    fixture.git(&["checkout", "-b", "feature"]);
+    fixture.commit_file("README", "hello world\n", "change greeting");
+    fixture.squash_merge("feature");
+
+    let run = fixture.quickprune(&[]);
+    assert_eq!(run.listed(), ["feature"]);
+    assert_eq!(fixture.branches(), ["main"]);
+}
+
+#[test]
+fn keeps_unmerged_branch() {
+    let fixture = Fixture::new();
+    fixture.git(&["checkout", "-b", "feature"]);
+    fixture.commit_file("README", "hello world\n", "change greeting");
+    fixture.git(&["checkout", "main"]);
+
+    let run = fixture.quickprune(&[]);
+    assert!(run.staging_file.is_none());
+    assert_eq!(fixture.branches(), ["feature", "main"]);
+}
+
+#[test]
+fn detects_squash_merge_followed_by_conflicting_change() {
+    let fixture = conflict_after_squash(1);
+    let run = fixture.quickprune(&[]);
+    assert_eq!(run.listed(), ["feature"]);
+}
+
+#[test]
+fn detects_squash_merge_followed_by_several_conflicting_changes() {
+    let fixture = conflict_after_squash(3);
+    let run = fixture.quickprune(&[]);
+    assert_eq!(run.listed(), ["feature"]);
+}
+
+#[test]
+fn history_search_respects_max_depth() {
+    let fixture = conflict_after_squash(3);
+    let run = fixture.quickprune(&["--max-depth", "2"]);
+    assert!(run.staging_file.is_none());
+    assert_eq!(fixture.branches(), ["feature", "main"]);
+
+    let max = usize::MAX.to_string();
+    let run = fixture.quickprune(&["--max-depth", &max, "--dry-run"]);
+    assert_eq!(run.stdout(), "Would delete branch 'feature'\n");
+
+    let run = fixture.quickprune(&["--max-depth", "3"]);
+    assert_eq!(run.listed(), ["feature"]);
+}
+
+#[test]
+fn keeps_conflicting_branch_that_was_never_merged() {
+    let fixture = Fixture::new();
+    fixture.git(&["checkout", "-b", "feature"]);
+    fixture.commit_file("README", "hello world\n", "change greeting");
+    fixture.git(&["checkout", "main"]);
+    fixture.commit_file("README", "hello there\n", "conflicting greeting");
+    fixture.commit_file("other", "unrelated\n", "unrelated change");
+    fixture.git(&["push", "origin", "main"]);
+
+    let run = fixture.quickprune(&[]);
+    assert!(run.staging_file.is_none());
+    assert_eq!(fixture.branches(), ["feature", "main"]);
+}
+
+#[test]
+fn lists_many_branches_in_order() {
+    let fixture = Fixture::new();
+    let mut merged = Vec::new();
+    for i in 0..12 {
+        let branch = format!("feature-{i:02}");
+        fixture.git(&["checkout", "-b", &branch, "main"]);
+        fixture.commit_file(&format!("{branch}.txt"), "content\n", "add file");
+        if i % 3 != 0 {
+            fixture.squash_merge(&branch);
+            merged.push(branch);
+        }
+    }
+    fixture.git(&["checkout", "main"]);
+
+    let run = fixture.quickprune(&[]);
+    assert_eq!(run.listed(), merged);
+}
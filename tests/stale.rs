@@ -0,0 +1,80 @@
+mod common;
+
+use common::Fixture;
+
+/// Builds a repo with two unmerged branches:
+/// `old-wip` with two commits from 100 days ago and `recent-wip`.
+fn fixture() -> Fixture {
+    let fixture = Fixture::new();
+    let hundred_days_ago = format!(
+        "@{}",
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            - 100 * 24 * 60 * 60
+    );
+    let env = [
+        ("GIT_COMMITTER_DATE", hundred_days_ago.as_str()),
+        ("GIT_AUTHOR_DATE", hundred_days_ago.as_str()),
+    ];
+
+    fixture.git(&["checkout", "-b", "old-wip"]);
+    fixture.commit_file_with_env("a", "a\n", "first wip commit", &env);
+    fixture.commit_file_with_env("b", "b\n", "second wip commit", &env);
+
+    fixture.git(&["checkout", "-b", "recent-wip", "main"]);
+    fixture.commit_file("c", "c\n", "recent wip commit");
+
+    fixture.git(&["checkout", "main"]);
+    fixture.commit_file("d", "d\n", "unrelated change");
+    fixture.git(&["push", "origin", "main"]);
+    fixture
+}
+
+#[test]
+fn lists_stale_branches_in_own_section() {
+    let fixture = fixture();
+    let run = fixture.quickprune(&["--stale", "30d"]);
+    assert!(run.listed().is_empty());
+    let staging_file = run.staging_file.unwrap();
+    let lines: Vec<_> = staging_file.lines().collect();
+    assert!(lines[0].starts_with("# recent-wip # "));
+    assert!(lines[0].ends_with(" old: recent wip commit"));
+    assert_eq!(lines[1], "");
+    assert_eq!(lines[2], "# Not merged, but no new commits for 4 weeks:");
+    let sha = fixture.git(&["rev-parse", "--short=7", "old-wip"]);
+    assert_eq!(
+        lines[3],
+        format!(
+            "# old-wip    # {}, 3 months old, 2 ahead, 1 behind origin/main: second wip commit",
+            sha.trim()
+        )
+    );
+    assert_eq!(fixture.branches(), ["main", "old-wip", "recent-wip"]);
+}
+
+#[test]
+fn without_stale_no_editor_is_opened() {
+    let fixture = fixture();
+    let run = fixture.quickprune(&[]);
+    assert!(run.staging_file.is_none());
+
+    let run = fixture.quickprune(&["--stale", "1y"]);
+    assert!(run.staging_file.is_none());
+}
+
+#[test]
+fn uncommented_stale_branch_is_deleted() {
+    let fixture = fixture();
+    fixture.quickprune_with_editor(&["--stale", "30d"], "sed -i 's/^# old-wip/old-wip/' \"$1\"");
+    assert_eq!(fixture.branches(), ["main", "recent-wip"]);
+}
+
+#[test]
+fn stale_branches_are_not_deleted_non_interactively() {
+    let fixture = fixture();
+    let run = fixture.quickprune(&["--stale", "30d", "--yes"]);
+    assert_eq!(run.stdout(), "Nothing to do.\n");
+    assert_eq!(fixture.branches(), ["main", "old-wip", "recent-wip"]);
+}
@@ -0,0 +1,88 @@
+mod common;
+
+use common::{conflict_after_squash, Fixture};
+
+#[test]
+fn patch_id_detects_squash_merge_without_history_search() {
+    let fixture = conflict_after_squash(2);
+
+    let run = fixture.quickprune(&["--max-depth", "0"]);
+    assert!(run.staging_file.is_none());
+
+    let run = fixture.quickprune(&["--max-depth", "0", "--strategy", "patch-id"]);
+    assert_eq!(run.listed(), ["feature"]);
+}
+
+#[test]
+fn patch_id_keeps_partially_merged_branch() {
+    let fixture = Fixture::new();
+    fixture.git(&["checkout", "-b", "feature"]);
+    fixture.commit_file("a", "a\n", "add a");
+    fixture.commit_file("b", "b\n", "add b");
+    fixture.git(&["checkout", "main"]);
+    fixture.git(&["cherry-pick", "feature~1"]);
+    fixture.git(&["push", "origin", "main"]);
+
+    let run = fixture.quickprune(&["--strategy", "patch-id"]);
+    assert!(run.staging_file.is_none());
+}
+
+#[test]
+fn patch_id_searches_long_history() {
+    let fixture = Fixture::new();
+    fixture.git(&["checkout", "-b", "feature"]);
+    fixture.commit_file("a", "a\n", "add a");
+    fixture.git(&["checkout", "main"]);
+    fixture.commit_many("b", 1500);
+    fixture.squash_merge("feature");
+    fixture.commit_many("b", 10);
+    fixture.git(&["push", "origin", "main"]);
+    // must not change the patches
+    fixture.git(&["config", "color.ui", "always"]);
+    fixture.git(&["config", "diff.noprefix", "true"]);
+
+    let run = fixture.quickprune(&["--strategy", "patch-id", "--dry-run"]);
+    assert_eq!(run.stdout(), "Would delete branch 'feature'\n");
+
+    let run = fixture.quickprune(&["--strategy", "cherry", "--patch-id-depth", "5"]);
+    assert!(run.staging_file.is_none());
+    assert!(run
+        .stderr()
+        .contains("Some branches forked from 'origin/main' more than 5 commits ago"));
+}
+
+/// Builds a repo where both commits of `feature` were rebased onto a
+/// diverged main.
+fn rebase_merged() -> Fixture {
+    let fixture = Fixture::new();
+    fixture.git(&["checkout", "-b", "feature"]);
+    fixture.commit_file("a", "a\n", "add a");
+    fixture.commit_file("b", "b\n", "add b");
+    fixture.git(&["checkout", "main"]);
+    fixture.commit_file("c", "c\n", "add c");
+    fixture.git(&["cherry-pick", "main..feature"]);
+    fixture.git(&["push", "origin", "main"]);
+    fixture
+}
+
+#[test]
+fn cherry_detects_rebase_merge() {
+    let fixture = rebase_merged();
+    let run = fixture.quickprune(&["--strategy", "cherry"]);
+    assert_eq!(run.listed(), ["feature"]);
+}
+
+#[test]
+fn cherry_keeps_squash_merge_of_several_commits() {
+    let fixture = Fixture::new();
+    fixture.git(&["checkout", "-b", "feature"]);
+    fixture.commit_file("a", "a\n", "add a");
+    fixture.commit_file("b", "b\n", "add b");
+    fixture.squash_merge("feature");
+
+    let run = fixture.quickprune(&["--strategy", "cherry"]);
+    assert!(run.staging_file.is_none());
+
+    let run = fixture.quickprune(&["--strategy", "all"]);
+    assert_eq!(run.listed(), ["feature"]);
+}
@@ -0,0 +1,104 @@
+mod common;
+
+use common::Fixture;
+
+#[test]
+fn restores_single_branch() {
+    let fixture = Fixture::with_pushed_squashed(&["a", "b"]);
+    let tip = fixture.git(&["rev-parse", "a"]);
+    fixture.quickprune(&["--yes"]);
+    assert_eq!(fixture.branches(), ["main"]);
+
+    let run = fixture.quickprune(&["undo", "a"]);
+    assert!(run.output.status.success());
+    assert!(run.stdout().contains("Restored branch 'a'"));
+    assert_eq!(fixture.branches(), ["a", "main"]);
+    assert_eq!(fixture.git(&["rev-parse", "a"]), tip);
+}
+
+#[test]
+fn restores_last_run() {
+    let fixture = Fixture::with_pushed_squashed(&["a", "b"]);
+    fixture.quickprune_with_editor(&[], "sed -i '/^b/d' \"$1\"");
+    assert_eq!(fixture.branches(), ["b", "main"]);
+    fixture.quickprune(&["--yes"]);
+    assert_eq!(fixture.branches(), ["main"]);
+
+    fixture.quickprune(&["undo", "--last-run"]);
+    assert_eq!(fixture.branches(), ["b", "main"]);
+}
+
+#[test]
+fn restores_remote_branches() {
+    let fixture = Fixture::with_pushed_squashed(&["a", "b"]);
+    fixture.quickprune(&["--yes", "-r"]);
+    assert_eq!(fixture.remote_branches(), ["main"]);
+
+    let run = fixture.quickprune(&["undo", "--last-run"]);
+    assert!(run.stdout().contains("Restored remote branch 'origin/a'"));
+    assert_eq!(fixture.branches(), ["a", "b", "main"]);
+    assert_eq!(fixture.remote_branches(), ["a", "b", "main"]);
+}
+
+#[test]
+fn backup_refs_are_kept_until_undo() {
+    let fixture = Fixture::with_pushed_squashed(&["a", "b"]);
+    fixture.quickprune(&["--yes", "--backup-refs"]);
+    let trash = fixture.git(&["for-each-ref", "--format=%(refname)", "refs/quickprune"]);
+    assert_eq!(trash, "refs/quickprune/trash/a\nrefs/quickprune/trash/b\n");
+
+    fixture.quickprune(&["undo", "a"]);
+    let trash = fixture.git(&["for-each-ref", "--format=%(refname)", "refs/quickprune"]);
+    assert_eq!(trash, "refs/quickprune/trash/b\n");
+}
+
+#[test]
+fn failed_deletion_is_not_logged() {
+    let fixture = Fixture::with_pushed_squashed(&["a", "b"]);
+    // git refuses to delete a locked ref
+    let lock = fixture.path().join(".git/refs/heads/a.lock");
+    std::fs::write(&lock, "").unwrap();
+    let run = fixture.quickprune(&["--yes"]);
+    assert!(run.stdout().contains("Failed to delete branch 'a'"));
+    std::fs::remove_file(lock).unwrap();
+
+    let run = fixture.quickprune(&["undo", "a"]);
+    assert!(!run.output.status.success());
+    fixture.quickprune(&["undo", "--last-run"]);
+    assert_eq!(fixture.branches(), ["a", "b", "main"]);
+}
+
+#[test]
+fn failed_backup_skips_only_that_branch() {
+    let fixture = Fixture::with_pushed_squashed(&["a", "b"]);
+    // conflicts with the backup ref of 'a'
+    fixture.git(&["update-ref", "refs/quickprune/trash/a/old", "main"]);
+    let run = fixture.quickprune(&["--yes", "--backup-refs"]);
+    assert!(run.output.status.success());
+    assert!(run.stdout().contains("Failed to delete branch 'a'"));
+    assert!(run.stdout().contains("Deleted branch 'b'"));
+    assert_eq!(fixture.branches(), ["a", "main"]);
+}
+
+#[test]
+fn fails_without_matching_log_entry() {
+    let fixture = Fixture::with_pushed_squashed(&["a", "b"]);
+    let run = fixture.quickprune(&["undo", "--last-run"]);
+    assert!(run.stderr().contains("nothing to undo"));
+
+    fixture.quickprune(&["--yes"]);
+    let run = fixture.quickprune(&["undo", "c"]);
+    assert!(!run.output.status.success());
+    assert!(run.stderr().contains("no deleted branch 'c'"));
+
+    let run = fixture.quickprune(&["undo"]);
+    assert!(!run.output.status.success());
+}
+
+#[test]
+fn dry_run_is_not_logged() {
+    let fixture = Fixture::with_pushed_squashed(&["a", "b"]);
+    fixture.quickprune(&["--dry-run"]);
+    let run = fixture.quickprune(&["undo", "--last-run"]);
+    assert!(!run.output.status.success());
+}
@@ -0,0 +1,104 @@
+mod common;
+
+use std::path::PathBuf;
+
+use common::Fixture;
+
+/// Builds a repo with a squash-merged branch `feature` that is checked out
+/// in a second worktree, whose path is returned as well.
+fn fixture() -> (Fixture, PathBuf) {
+    let fixture = Fixture::new();
+    fixture.git(&["checkout", "-b", "feature"]);
+    fixture.commit_file("a", "a\n", "add a");
+    fixture.squash_merge("feature");
+    let worktree = fixture.path().parent().unwrap().join("worktree");
+    fixture.git(&["worktree", "add", worktree.to_str().unwrap(), "feature"]);
+    (fixture, worktree.canonicalize().unwrap())
+}
+
+#[test]
+fn branch_in_worktree_is_marked_and_kept() {
+    let (fixture, worktree) = fixture();
+    let run = fixture.quickprune(&[]);
+    assert!(run.listed().is_empty());
+    let staging_file = run.staging_file.unwrap();
+    assert!(staging_file.contains("# Merged, but checked out in a worktree:"));
+    assert!(staging_file.contains(&format!("checked out in {}", worktree.display())));
+    assert_eq!(fixture.branches(), ["feature", "main"]);
+}
+
+#[test]
+fn remove_worktree_removes_clean_worktree_and_branch() {
+    let (fixture, worktree) = fixture();
+    let run = fixture.quickprune_with_editor(&[], "sed -i 's/^# feature/w feature/' \"$1\"");
+    assert!(run.stdout().contains("Removed worktree"));
+    assert!(!worktree.exists());
+    assert_eq!(fixture.branches(), ["main"]);
+}
+
+#[test]
+fn remove_worktree_refuses_dirty_worktree() {
+    let (fixture, worktree) = fixture();
+    std::fs::write(worktree.join("untracked"), "wip\n").unwrap();
+    let run = fixture.quickprune_with_editor(&[], "sed -i 's/^# feature/w feature/' \"$1\"");
+    assert!(run.stdout().contains("Aborted"));
+    assert!(run.staging_file.unwrap().contains(&format!(
+        "worktree '{}' has uncommitted changes",
+        worktree.display()
+    )));
+    assert!(worktree.exists());
+    assert_eq!(fixture.branches(), ["feature", "main"]);
+}
+
+#[test]
+fn deleting_branch_in_worktree_is_a_problem() {
+    let (fixture, _) = fixture();
+    let run = fixture.quickprune_with_editor(&[], "sed -i 's/^# feature/feature/' \"$1\"");
+    assert!(run
+        .staging_file
+        .unwrap()
+        .contains("branch 'feature' is checked out in"));
+    assert_eq!(fixture.branches(), ["feature", "main"]);
+}
+
+#[test]
+fn remove_worktree_refuses_unmerged_branch() {
+    let (fixture, worktree) = fixture();
+    fixture.git_in(&worktree, &["checkout", "-b", "unmerged"]);
+    std::fs::write(worktree.join("b"), "b\n").unwrap();
+    fixture.git_in(&worktree, &["add", "b"]);
+    fixture.git_in(&worktree, &["commit", "-m", "add b"]);
+    let run = fixture.quickprune_with_editor(&["-e"], "printf 'w unmerged\\n' > \"$1\"");
+    assert!(run
+        .staging_file
+        .unwrap()
+        .contains("branch 'unmerged' is not merged, refusing to remove its worktree"));
+    assert!(worktree.exists());
+}
+
+#[test]
+fn deleted_worktree_does_not_block_other_branches() {
+    let (fixture, worktree) = fixture();
+    fixture.git(&["checkout", "-b", "other", "main"]);
+    fixture.commit_file("b", "b\n", "add b");
+    fixture.squash_merge("other");
+    std::fs::remove_dir_all(&worktree).unwrap();
+
+    let run = fixture.quickprune(&[]);
+    assert_eq!(run.listed(), ["other"]);
+    assert!(run.output.status.success(), "{}", run.stderr());
+    assert_eq!(fixture.branches(), ["feature", "main"]);
+}
+
+#[test]
+fn remove_worktree_refuses_deleted_worktree() {
+    let (fixture, worktree) = fixture();
+    std::fs::remove_dir_all(&worktree).unwrap();
+    let run = fixture.quickprune_with_editor(&[], "sed -i 's/^# feature/w feature/' \"$1\"");
+    assert!(run.stdout().contains("Aborted"));
+    assert!(run.staging_file.unwrap().contains(&format!(
+        "worktree '{}' no longer exists, run 'git worktree prune'",
+        worktree.display()
+    )));
+    assert_eq!(fixture.branches(), ["feature", "main"]);
+}